@@ -4,6 +4,10 @@
 //! must implement, providing a consistent interface for solving both parts of
 //! each day's challenge.
 
+use anyhow::{Result, bail};
+
+use crate::params::Params;
+
 /// Trait implemented by each day's puzzle solution.
 ///
 /// Each day in Advent of Code consists of two parts (A and B) that operate
@@ -26,4 +30,55 @@ pub trait Day {
     /// # Returns
     /// The solution as a string representation.
     fn part_b(lines: &[String]) -> String;
+
+    /// Solves part A of the puzzle with parameters from the command line.
+    ///
+    /// Days whose solutions can be tuned override this; the default ignores
+    /// the parameters and calls [`Day::part_a`].
+    ///
+    /// # Arguments
+    /// * `lines` - The puzzle input as a slice of strings, one per line.
+    /// * `params` - Extra `key=value` parameters from the command line.
+    ///
+    /// # Returns
+    /// The solution as a string representation, or an error if a parameter
+    /// is invalid.
+    fn part_a_with(lines: &[String], params: &Params) -> Result<String> {
+        let _ = params;
+        Ok(Self::part_a(lines))
+    }
+
+    /// Solves part B of the puzzle with parameters from the command line.
+    ///
+    /// Days whose solutions can be tuned override this; the default ignores
+    /// the parameters and calls [`Day::part_b`].
+    ///
+    /// # Arguments
+    /// * `lines` - The puzzle input as a slice of strings, one per line.
+    /// * `params` - Extra `key=value` parameters from the command line.
+    ///
+    /// # Returns
+    /// The solution as a string representation, or an error if a parameter
+    /// is invalid.
+    fn part_b_with(lines: &[String], params: &Params) -> Result<String> {
+        let _ = params;
+        Ok(Self::part_b(lines))
+    }
+
+    /// Runs a day-specific output mode instead of the two parts.
+    ///
+    /// Days that offer extra reports (e.g. explaining how an answer was
+    /// reached) override this; the default rejects every mode.
+    ///
+    /// # Arguments
+    /// * `lines` - The puzzle input as a slice of strings, one per line.
+    /// * `mode` - The name of the mode requested on the command line.
+    /// * `params` - Extra `key=value` parameters from the command line.
+    ///
+    /// # Returns
+    /// The mode's output, or an error if the mode isn't supported.
+    fn mode(lines: &[String], mode: &str, params: &Params) -> Result<String> {
+        let _ = (lines, params);
+        bail!("Mode [{}] isn't supported", mode)
+    }
 }
@@ -4,6 +4,22 @@
 //!
 //! ## Input Format
 //! Comma-separated ranges in the format `start-end`, e.g., `10-100,200-300`.
+//! Overlapping ranges are merged, so each ID is counted at most once.
+//!
+//! ## Parameters
+//! * `base` - The base (2-36) the IDs are written and checked in; defaults
+//!   to 10. Sums are always printed in decimal.
+//! * `threads` - Number of threads used to sum the ranges; `1` runs
+//!   sequentially, and the default `0` lets rayon choose.
+//! * `chunk` - Ranges larger than this many IDs are split into chunks that
+//!   are summed in parallel, at most one chunk per thread for each digit
+//!   length; defaults to 1,000,000.
+//!
+//! ## Modes
+//! * `list` - Lists every matching ID in each range with its primitive
+//!   period (smallest repeating block and repetition count), then a
+//!   histogram of matches by period length. `part=a` lists only "twice"
+//!   numbers; the default `part=b` lists all "repeat" numbers.
 //!
 //! ## Part A
 //! Sums all "twice" numbers in the ranges - numbers where the first half of digits
@@ -12,100 +28,515 @@
 //! ## Part B
 //! Sums all "repeat" numbers - numbers that can be divided into 2 or more identical
 //! chunks (e.g., 111, 1212, 121212).
+//!
+//! Both parts generate the matching numbers arithmetically per digit length
+//! instead of scanning every number in each range. Ranges are split at
+//! digit-length boundaries and into a few chunks each, then summed with
+//! rayon; sums are exact integers, so the result is identical to the
+//! sequential path whatever the thread count.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, anyhow, bail};
+use rayon::ThreadPoolBuilder;
+use rayon::prelude::*;
 
 use crate::day::Day;
+use crate::params::Params;
+
+/// Default number of IDs per chunk when splitting ranges for parallel sums.
+const CHUNK_SIZE: u64 = 1_000_000;
 
 /// Solution for Day 2: Repeating Number Patterns puzzle.
 pub struct Day2 {}
 
+/// A matching ID together with its primitive period.
+#[derive(Debug, PartialEq, Eq)]
+struct Match {
+    /// The ID itself
+    id: u64,
+    /// The smallest block of digits the ID repeats
+    block: u64,
+    /// How many times the block repeats
+    count: u32,
+    /// Number of digits in the block
+    period: u32,
+}
+
 impl Day for Day2 {
     fn part_a(lines: &[String]) -> String {
-        lines
-            .iter()
-            .flat_map(|line| line.split(','))
-            .filter(|r| !r.is_empty())
-            .map(|line| {
-                let mut vals = line.split('-').map(|n| n.parse().unwrap());
-                (vals.next().unwrap(), vals.next().unwrap())
-            })
-            .flat_map(|(f, s)| (f..=s).filter(|v| is_twice(*v)))
-            .sum::<u64>()
-            .to_string()
+        Day2::part_a_with(lines, &Params::default()).unwrap()
     }
 
     fn part_b(lines: &[String]) -> String {
-        let ranges: Vec<&str> = lines
-            .iter()
-            .flat_map(|line| line.split(','))
-            .filter(|r| !r.is_empty())
-            .collect();
+        Day2::part_b_with(lines, &Params::default()).unwrap()
+    }
+
+    fn part_a_with(lines: &[String], params: &Params) -> Result<String> {
+        let base = parse_base(params)?;
+        let ranges = parse_ranges(lines, base)?;
+
+        Ok(sum_ranges(&ranges, params, base, |f, s| sum_twice(f, s, base))?.to_string())
+    }
 
-        let ranges: Vec<(u64, u64)> = ranges
-            .into_iter()
-            .map(|line| {
-                let mut vals = line.split('-');
-                (vals.next().unwrap(), vals.next().unwrap())
-            })
-            .map(|(f, s)| (f.parse().unwrap(), s.parse().unwrap()))
-            .collect();
+    fn part_b_with(lines: &[String], params: &Params) -> Result<String> {
+        let base = parse_base(params)?;
+        let ranges = parse_ranges(lines, base)?;
 
-        let repeat_counts: u64 = ranges
-            .into_iter()
-            .flat_map(|(f, s)| (f..=s).filter(|v| is_repeat(*v)))
-            .sum();
+        Ok(sum_ranges(&ranges, params, base, |f, s| sum_repeat(f, s, base))?.to_string())
+    }
+
+    fn mode(lines: &[String], mode: &str, params: &Params) -> Result<String> {
+        match mode {
+            "list" => {
+                let base = parse_base(params)?;
+                let part: String = params.get_or("part", "b".to_string())?;
 
-        format!("{}", repeat_counts)
+                let twice_only = match part.as_str() {
+                    "a" => true,
+                    "b" => false,
+                    _ => bail!("Part [{}] must be a or b", part),
+                };
+
+                Ok(list_matches(&parse_ranges(lines, base)?, base, twice_only))
+            }
+            _ => bail!("Mode [{}] isn't supported by day2", mode),
+        }
     }
 }
 
-/// Checks if a number is a "twice" number.
+/// Sums `sum` over every range, splitting large ranges into chunks that are
+/// processed in parallel according to the `threads` and `chunk` parameters.
 ///
-/// A twice number has an even number of digits where the first half
-/// exactly matches the second half (e.g., 77, 1212, 123123).
-fn is_twice(num: u64) -> bool {
-    let num = num.to_string();
+/// # Arguments
+/// * `ranges` - The disjoint ranges to sum over
+/// * `params` - Parameters holding the thread count and chunk size
+/// * `base` - The base IDs are written in, used to find digit-length boundaries
+/// * `sum` - Sums the matching IDs in a single `start..=end` chunk
+///
+/// # Returns
+/// The total over all chunks, which is the same for any thread count.
+fn sum_ranges<F>(ranges: &[(u64, u64)], params: &Params, base: u32, sum: F) -> Result<u128>
+where
+    F: Fn(u64, u64) -> u128 + Sync,
+{
+    let threads: usize = params.get_or("threads", 0)?;
+    let chunk_size: u64 = params.get_or("chunk", CHUNK_SIZE)?;
 
-    if (num.len() & 1) == 1 {
-        return false;
+    if chunk_size == 0 {
+        bail!("Chunk size must be at least 1");
     }
 
-    let idx = num.len() / 2;
-    let left = &num[..idx];
-    let right = &num[idx..];
+    if threads == 1 {
+        let chunks = split_ranges(ranges, base, chunk_size, 1);
+        return Ok(chunks.iter().map(|&(f, s)| sum(f, s)).sum());
+    }
+
+    let pool = ThreadPoolBuilder::new().num_threads(threads).build()?;
+    let chunks = split_ranges(ranges, base, chunk_size, pool.current_num_threads());
 
-    left == right
+    Ok(pool.install(|| chunks.par_iter().map(|&(f, s)| sum(f, s)).sum()))
 }
 
-/// Checks if a number can be split into repeating identical chunks.
+/// Splits ranges into chunks for parallel summing.
 ///
-/// Tries all possible chunk counts (2 to digit length) to find any
-/// valid repeating pattern (e.g., 111 splits into "1","1","1").
-fn is_repeat(num: u64) -> bool {
-    let num = num.to_string();
-    for len in 2..=num.len() {
-        if is_repeat_n(&num, len) {
-            return true;
+/// Each range is first cut where the digit count changes, since the sums
+/// are computed per digit length anyway. Each of those pieces is then split
+/// into chunks of about `chunk_size` IDs, but never more than `max_chunks`
+/// of them, so huge ranges don't turn into huge chunk lists: the sums cost
+/// the same however many IDs a chunk holds.
+fn split_ranges(
+    ranges: &[(u64, u64)],
+    base: u32,
+    chunk_size: u64,
+    max_chunks: usize,
+) -> Vec<(u64, u64)> {
+    let mut chunks = vec![];
+
+    for &(start, end) in ranges {
+        for (lo, hi) in digit_bands(start, end, base) {
+            let span = (hi - lo) as u128 + 1;
+            let count = span
+                .div_ceil(chunk_size as u128)
+                .min(max_chunks.max(1) as u128);
+            let size = span.div_ceil(count);
+
+            let mut chunk_start = lo as u128;
+
+            while chunk_start <= hi as u128 {
+                let chunk_end = (chunk_start + size - 1).min(hi as u128);
+                chunks.push((chunk_start as u64, chunk_end as u64));
+                chunk_start = chunk_end + 1;
+            }
+        }
+    }
+
+    chunks
+}
+
+/// Cuts `start..=end` into consecutive pieces whose IDs all have the same
+/// number of digits in the given base.
+fn digit_bands(start: u64, end: u64, base: u32) -> Vec<(u64, u64)> {
+    let mut bands = vec![];
+    let mut lo = start;
+
+    for len in digit_count(start, base)..=digit_count(end, base) {
+        let hi = ((base as u128).pow(len) - 1).min(end as u128) as u64;
+        bands.push((lo, hi));
+
+        if hi == end {
+            break;
         }
+
+        lo = hi + 1;
     }
-    false
+
+    bands
 }
 
-/// Checks if a string can be split into exactly `num_splits` identical chunks.
+/// Lists every matching ID per range with its primitive period, followed by
+/// a histogram of match counts by period length.
 ///
-/// Returns false if the string length isn't evenly divisible by `num_splits`.
-fn is_repeat_n(num: &str, num_splits: usize) -> bool {
-    if num.len() % num_splits != 0 {
-        return false;
+/// # Arguments
+/// * `ranges` - The ranges to search
+/// * `base` - The base IDs are written in
+/// * `twice_only` - Match only "twice" numbers (part A) instead of all
+///   "repeat" numbers (part B)
+fn list_matches(ranges: &[(u64, u64)], base: u32, twice_only: bool) -> String {
+    let mut out = vec![];
+    let mut histogram: BTreeMap<u32, usize> = BTreeMap::new();
+
+    for &(start, end) in ranges {
+        out.push(format!(
+            "{}-{}:",
+            to_base(start as u128, base),
+            to_base(end as u128, base)
+        ));
+
+        for m in matches_in(start, end, base, twice_only) {
+            *histogram.entry(m.period).or_default() += 1;
+
+            out.push(format!(
+                "  {} = {} x {}",
+                to_base(m.id as u128, base),
+                to_base(m.block as u128, base),
+                m.count
+            ));
+        }
+    }
+
+    out.push("period histogram:".to_string());
+    for (period, count) in histogram {
+        out.push(format!("  {}: {}", period, count));
+    }
+
+    out.join("\n")
+}
+
+/// Generates the matching IDs in `start..=end`, in ascending order.
+///
+/// Like [`sum_repeat`], each repeat number is generated once, from its
+/// primitive period. For twice numbers only the half-length period is
+/// generated, but the primitive period is still reported.
+fn matches_in(start: u64, end: u64, base: u32, twice_only: bool) -> Vec<Match> {
+    let mut matches = vec![];
+
+    for len in 2..=digit_count(end, base) {
+        for period in (1..len).filter(|p| len.is_multiple_of(*p)) {
+            if twice_only && period * 2 != len {
+                continue;
+            }
+
+            for id in periodic_numbers(start, end, len, period, base) {
+                let m = primitive_period(id, base);
+
+                if twice_only || m.period == period {
+                    matches.push(m);
+                }
+            }
+        }
+    }
+
+    matches.sort_by_key(|m| m.id);
+
+    matches
+}
+
+/// Finds the smallest block that a number repeats in the given base.
+///
+/// Numbers that don't repeat at all are their own block, repeated once.
+fn primitive_period(id: u64, base: u32) -> Match {
+    let len = digit_count(id, base);
+
+    let period = (1..=len)
+        .filter(|p| len.is_multiple_of(*p))
+        .find(|p| is_repeat_n(id, base, len / p))
+        .unwrap();
+
+    Match {
+        id,
+        block: (id as u128 % (base as u128).pow(period)) as u64,
+        count: len / period,
+        period,
+    }
+}
+
+/// Formats a number in the given base using `0-9a-z` digits.
+fn to_base(mut num: u128, base: u32) -> String {
+    let mut digits = vec![];
+
+    loop {
+        digits.push(char::from_digit((num % base as u128) as u32, base).unwrap());
+        num /= base as u128;
+
+        if num == 0 {
+            break;
+        }
+    }
+
+    digits.iter().rev().collect()
+}
+
+/// Reads the `base` parameter, defaulting to decimal.
+fn parse_base(params: &Params) -> Result<u32> {
+    let base = params.get_or("base", 10)?;
+
+    if !(2..=36).contains(&base) {
+        bail!("Base [{}] must be between 2 and 36", base);
+    }
+
+    Ok(base)
+}
+
+/// Parses the comma-separated `start-end` ranges from every line.
+///
+/// Range bounds are written in the given base. Every bad entry is reported
+/// with its line and column, and overlapping ranges are merged so each ID
+/// is only counted once.
+fn parse_ranges(lines: &[String], base: u32) -> Result<Vec<(u64, u64)>> {
+    let mut ranges = vec![];
+    let mut errors = vec![];
+
+    for (i, line) in lines.iter().enumerate() {
+        let mut column = 1;
+
+        for entry in line.split(',') {
+            if !entry.is_empty() {
+                match parse_range(entry, base) {
+                    Ok(range) => ranges.push(range),
+                    Err(e) => errors.push(format!(
+                        "line {}, column {}: `{}`: {}",
+                        i + 1,
+                        column,
+                        entry,
+                        e
+                    )),
+                }
+            }
+
+            column += entry.chars().count() + 1;
+        }
+    }
+
+    if !errors.is_empty() {
+        bail!("Invalid ranges:\n{}", errors.join("\n"));
+    }
+
+    Ok(merge_ranges(ranges))
+}
+
+/// Parses a single `start-end` range, checking that `start <= end`.
+fn parse_range(entry: &str, base: u32) -> Result<(u64, u64)> {
+    let Some((start, end)) = entry.split_once('-') else {
+        bail!("expected `start-end`");
+    };
+
+    let start = parse_id(start, base)?;
+    let end = parse_id(end, base)?;
+
+    if start > end {
+        bail!("start is greater than end");
+    }
+
+    Ok((start, end))
+}
+
+/// Parses an ID, accepting only digits of the given base.
+fn parse_id(id: &str, base: u32) -> Result<u64> {
+    if id.is_empty() {
+        bail!("missing number");
+    }
+
+    if let Some(c) = id.chars().find(|c| c.to_digit(base).is_none()) {
+        bail!("`{}` isn't a base {} digit", c, base);
+    }
+
+    u64::from_str_radix(id, base).map_err(|_| anyhow!("`{}` is too large", id))
+}
+
+/// Merges overlapping and adjacent ranges into a sorted, disjoint list.
+fn merge_ranges(mut ranges: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    ranges.sort();
+
+    let mut merged: Vec<(u64, u64)> = vec![];
+
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
     }
 
-    let chunk_size = num.len() / num_splits;
+    merged
+}
+
+/// Sums every "twice" number in `start..=end`.
+///
+/// Rather than testing each number, the twice numbers of each even digit
+/// length are generated directly (see [`sum_periodic`]).
+fn sum_twice(start: u64, end: u64, base: u32) -> u128 {
+    (1..=digit_count(end, base) / 2)
+        .map(|half| sum_periodic(start, end, half * 2, half, base))
+        .sum()
+}
+
+/// Sums every "repeat" number in `start..=end`.
+///
+/// A number can repeat with several periods (e.g. 111111 is `1` six times,
+/// `11` three times and `111` twice), so each number is attributed only to
+/// its primitive (smallest) period to avoid counting it more than once.
+fn sum_repeat(start: u64, end: u64, base: u32) -> u128 {
+    let mut sum = 0;
+
+    for len in 2..=digit_count(end, base) {
+        for period in (1..len).filter(|p| len.is_multiple_of(*p)) {
+            sum += sum_primitive(start, end, len, period, base);
+        }
+    }
+
+    sum
+}
+
+/// Sums the `len`-digit numbers in `start..=end` whose smallest repeating
+/// block is exactly `period` digits long.
+///
+/// Inclusion-exclusion across periods: the numbers built from a
+/// `period`-digit block include those built from any shorter block whose
+/// length divides `period`, so those are subtracted back out.
+fn sum_primitive(start: u64, end: u64, len: u32, period: u32, base: u32) -> u128 {
+    let mut sum = sum_periodic(start, end, len, period, base);
+
+    for shorter in (1..period).filter(|p| period.is_multiple_of(*p)) {
+        sum -= sum_primitive(start, end, len, shorter, base);
+    }
+
+    sum
+}
+
+/// Sums the `len`-digit numbers in `start..=end` made of a `period`-digit
+/// block repeated `len / period` times.
+///
+/// Every such number is `block * multiplier`, where the multiplier is the
+/// repunit-style `1 0..0 1 0..0 1` (e.g. `10101` for a 2-digit block
+/// repeated three times). The valid blocks therefore form a contiguous
+/// range, and their sum is an arithmetic series.
+fn sum_periodic(start: u64, end: u64, len: u32, period: u32, base: u32) -> u128 {
+    let (lo, hi, multiplier) = block_range(start, end, len, period, base);
+
+    if lo > hi {
+        return 0;
+    }
+
+    multiplier * (lo + hi) * (hi - lo + 1) / 2
+}
+
+/// Generates the `len`-digit numbers in `start..=end` made of a
+/// `period`-digit block repeated `len / period` times, in ascending order.
+fn periodic_numbers(
+    start: u64,
+    end: u64,
+    len: u32,
+    period: u32,
+    base: u32,
+) -> impl Iterator<Item = u64> {
+    let (lo, hi, multiplier) = block_range(start, end, len, period, base);
+
+    (lo..=hi).map(move |block| (block * multiplier) as u64)
+}
+
+/// Finds the blocks whose repetitions land in `start..=end`.
+///
+/// # Returns
+/// A tuple of (first_block, last_block, multiplier); the range is empty if
+/// `first_block > last_block`.
+fn block_range(start: u64, end: u64, len: u32, period: u32, base: u32) -> (u128, u128, u128) {
+    let base = base as u128;
+    let multiplier = repeat_multiplier(len, period, base);
+
+    let min_block = base.pow(period - 1);
+    let max_block = base.pow(period) - 1;
+
+    let lo = min_block.max((start as u128).div_ceil(multiplier));
+    let hi = max_block.min(end as u128 / multiplier);
+
+    (lo, hi, multiplier)
+}
+
+/// Computes the multiplier that repeats a `period`-digit block out to `len`
+/// digits, i.e. `(base^len - 1) / (base^period - 1)`.
+fn repeat_multiplier(len: u32, period: u32, base: u128) -> u128 {
+    (base.pow(len) - 1) / (base.pow(period) - 1)
+}
+
+/// Counts the digits of a number in the given base.
+fn digit_count(num: u64, base: u32) -> u32 {
+    num.checked_ilog(base as u64).unwrap_or(0) + 1
+}
+
+/// Checks if a number is a "twice" number in the given base.
+///
+/// This is the brute-force definition, kept as a reference for
+/// [`sum_twice`].
+///
+/// A twice number has an even number of digits where the first half
+/// exactly matches the second half (e.g., 77, 1212, 123123).
+#[cfg(test)]
+fn is_twice(num: u64, base: u32) -> bool {
+    is_repeat_n(num, base, 2)
+}
+
+/// Checks if a number can be split into repeating identical chunks in the
+/// given base.
+///
+/// This is the brute-force definition, kept as a reference for
+/// [`sum_repeat`].
+///
+/// Tries all possible chunk counts (2 to digit length) to find any
+/// valid repeating pattern (e.g., 111 splits into "1","1","1").
+#[cfg(test)]
+fn is_repeat(num: u64, base: u32) -> bool {
+    (2..=digit_count(num, base)).any(|splits| is_repeat_n(num, base, splits))
+}
+
+/// Checks if a number's digits in the given base split into exactly
+/// `num_splits` identical chunks.
+///
+/// The lowest chunk is peeled off arithmetically and repeated with
+/// [`repeat_multiplier`]; the number matches if that rebuilds it exactly.
+/// Returns false if the digit count isn't evenly divisible by `num_splits`.
+fn is_repeat_n(num: u64, base: u32, num_splits: u32) -> bool {
+    let len = digit_count(num, base);
+
+    if !len.is_multiple_of(num_splits) {
+        return false;
+    }
 
-    let chars: Vec<char> = num.chars().collect();
-    let parts: Vec<_> = chars.chunks(chunk_size).collect();
+    let chunk_size = len / num_splits;
+    let base = base as u128;
 
-    let mut part_pairs = parts.windows(2);
+    let chunk = num as u128 % base.pow(chunk_size);
 
-    part_pairs.all(|pp| pp[0] == pp[1])
+    chunk * repeat_multiplier(len, chunk_size, base) == num as u128
 }
 
 #[cfg(test)]
@@ -114,48 +545,383 @@ mod tests {
 
     #[test]
     fn test_is_twice_true() {
-        assert!(is_twice(77));
-        assert!(is_twice(1212));
-        assert!(is_twice(123123));
-        assert!(is_twice(11));
+        assert!(is_twice(77, 10));
+        assert!(is_twice(1212, 10));
+        assert!(is_twice(123123, 10));
+        assert!(is_twice(11, 10));
     }
 
     #[test]
     fn test_is_twice_false() {
-        assert!(!is_twice(123)); // odd length
-        assert!(!is_twice(1234)); // halves don't match
-        assert!(!is_twice(1213)); // close but not equal
-        assert!(!is_twice(7)); // single digit
+        assert!(!is_twice(123, 10)); // odd length
+        assert!(!is_twice(1234, 10)); // halves don't match
+        assert!(!is_twice(1213, 10)); // close but not equal
+        assert!(!is_twice(7, 10)); // single digit
     }
 
     #[test]
     fn test_is_repeat_n_true() {
-        assert!(is_repeat_n("11", 2));
-        assert!(is_repeat_n("111", 3));
-        assert!(is_repeat_n("1212", 2));
-        assert!(is_repeat_n("abcabcabc", 3));
+        assert!(is_repeat_n(11, 10, 2));
+        assert!(is_repeat_n(111, 10, 3));
+        assert!(is_repeat_n(1212, 10, 2));
+        assert!(is_repeat_n(0xabcabcabc, 16, 3));
     }
 
     #[test]
     fn test_is_repeat_n_false() {
-        assert!(!is_repeat_n("12", 2)); // chunks don't match
-        assert!(!is_repeat_n("123", 2)); // not evenly divisible
-        assert!(!is_repeat_n("1213", 2)); // chunks don't match
+        assert!(!is_repeat_n(12, 10, 2)); // chunks don't match
+        assert!(!is_repeat_n(123, 10, 2)); // not evenly divisible
+        assert!(!is_repeat_n(1213, 10, 2)); // chunks don't match
     }
 
     #[test]
     fn test_is_repeat_true() {
-        assert!(is_repeat(11));
-        assert!(is_repeat(111));
-        assert!(is_repeat(1212));
-        assert!(is_repeat(123123));
-        assert!(is_repeat(121212));
+        assert!(is_repeat(11, 10));
+        assert!(is_repeat(111, 10));
+        assert!(is_repeat(1212, 10));
+        assert!(is_repeat(123123, 10));
+        assert!(is_repeat(121212, 10));
     }
 
     #[test]
     fn test_is_repeat_false() {
-        assert!(!is_repeat(12));
-        assert!(!is_repeat(123));
-        assert!(!is_repeat(1234));
+        assert!(!is_repeat(12, 10));
+        assert!(!is_repeat(123, 10));
+        assert!(!is_repeat(1234, 10));
+    }
+
+    fn brute_twice(start: u64, end: u64, base: u32) -> u128 {
+        (start..=end)
+            .filter(|v| is_twice(*v, base))
+            .map(|v| v as u128)
+            .sum()
+    }
+
+    fn brute_repeat(start: u64, end: u64, base: u32) -> u128 {
+        (start..=end)
+            .filter(|v| is_repeat(*v, base))
+            .map(|v| v as u128)
+            .sum()
+    }
+
+    #[test]
+    fn test_is_repeat_other_bases() {
+        assert!(is_twice(0b1010, 2));
+        assert!(!is_twice(0b1011, 2));
+        assert!(is_repeat(0b111, 2));
+        assert!(is_twice(0xabab, 16));
+        assert!(!is_repeat(0xabcd, 16));
+        // "zz" in base 36
+        assert!(is_twice(35 * 36 + 35, 36));
+    }
+
+    #[test]
+    fn test_sum_periodic() {
+        // 2-digit blocks repeated twice within 1000..=1300: 1010, 1111, 1212
+        assert_eq!(sum_periodic(1000, 1300, 4, 2, 10), 1010 + 1111 + 1212);
+        // 1-digit blocks repeated three times: 111..999
+        assert_eq!(sum_periodic(0, 1000, 3, 1, 10), 111 * 45);
+        assert_eq!(sum_periodic(1320, 1400, 4, 2, 10), 0);
+        // Binary 1010 and 1111
+        assert_eq!(sum_periodic(0, 15, 4, 2, 2), 10 + 15);
+    }
+
+    #[test]
+    fn test_sum_primitive_excludes_shorter_periods() {
+        // 6-digit numbers with period 2: 101010..999999 step 10101, minus
+        // the period-1 numbers 111111..999999
+        let all = sum_periodic(0, 999_999, 6, 2, 10);
+        let ones = sum_periodic(0, 999_999, 6, 1, 10);
+        assert_eq!(sum_primitive(0, 999_999, 6, 2, 10), all - ones);
+    }
+
+    #[test]
+    fn test_sum_matches_brute_force_exhaustively() {
+        for base in [2, 3, 7, 10, 16, 36] {
+            // Prefix sums of the brute-force answers, so every range is cheap to check
+            let limit = 2000;
+            let mut twice = vec![0u128; limit + 1];
+            let mut repeat = vec![0u128; limit + 1];
+            for n in 0..limit {
+                twice[n + 1] = twice[n] + brute_twice(n as u64, n as u64, base);
+                repeat[n + 1] = repeat[n] + brute_repeat(n as u64, n as u64, base);
+            }
+
+            for start in (0..limit).step_by(3) {
+                for end in (start..limit).step_by(7) {
+                    let (s, e) = (start as u64, end as u64);
+                    assert_eq!(sum_twice(s, e, base), twice[end + 1] - twice[start]);
+                    assert_eq!(sum_repeat(s, e, base), repeat[end + 1] - repeat[start]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sum_matches_brute_force_large_ranges() {
+        let ranges = [
+            (95, 115),
+            (998, 1012),
+            (99_990, 100_010),
+            (121_000, 123_500),
+            (999_000, 1_001_500),
+            (1_188_511_880, 1_188_511_890),
+            (2_121_212_118, 2_121_212_124),
+        ];
+
+        for (start, end) in ranges {
+            for base in [2, 10, 16] {
+                assert_eq!(sum_twice(start, end, base), brute_twice(start, end, base));
+                assert_eq!(sum_repeat(start, end, base), brute_repeat(start, end, base));
+            }
+        }
+    }
+
+    #[test]
+    fn test_sum_near_u64_max() {
+        // 20-digit numbers (and 64 binary digits) are handled without overflow
+        let top = u64::MAX;
+        assert_eq!(sum_twice(top - 10, top, 10), 0);
+        assert_eq!(
+            sum_repeat(11_111_111_111_111_111_110, 11_111_111_111_111_111_112, 10),
+            11_111_111_111_111_111_111
+        );
+        assert_eq!(sum_repeat(top - 1, top, 2), top as u128);
+        assert_eq!(sum_twice(top - 1, top, 16), top as u128);
+    }
+
+    #[test]
+    fn test_parts_small_input() {
+        let input = vec![
+            "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,".to_string(),
+            "1698522-1698528,446443-446449,38593856-38593862,565653-565659,".to_string(),
+            "824824821-824824827,2121212118-2121212124".to_string(),
+        ];
+        assert_eq!(Day2::part_a(&input), "1227775554");
+        assert_eq!(Day2::part_b(&input), "4174379265");
+    }
+
+    #[test]
+    fn test_parts_with_base() {
+        // Binary 1000..1111 holds 1010 and 1111 (both twice, both repeat)
+        // and hex f0..100 holds only ff
+        let input = vec!["1000-1111".to_string()];
+        let binary = Params::from_strs(&["base=2"]);
+        assert_eq!(Day2::part_a_with(&input, &binary).unwrap(), "25");
+        assert_eq!(Day2::part_b_with(&input, &binary).unwrap(), "25");
+
+        let input = vec!["f0-100".to_string()];
+        let hex = Params::from_strs(&["base=16"]);
+        assert_eq!(Day2::part_a_with(&input, &hex).unwrap(), "255");
+    }
+
+    #[test]
+    fn test_invalid_base() {
+        let input = vec!["1-2".to_string()];
+        for base in ["1", "37", "ten"] {
+            let params = Params::parse(&[format!("base={}", base)]).unwrap();
+            assert!(Day2::part_a_with(&input, &params).is_err());
+        }
+    }
+
+    #[test]
+    fn test_primitive_period() {
+        let m = primitive_period(121212, 10);
+        assert_eq!((m.block, m.count, m.period), (12, 3, 2));
+
+        let m = primitive_period(111111, 10);
+        assert_eq!((m.block, m.count, m.period), (1, 6, 1));
+
+        let m = primitive_period(1234, 10);
+        assert_eq!((m.block, m.count, m.period), (1234, 1, 4));
+
+        let m = primitive_period(0xabab, 16);
+        assert_eq!((m.block, m.count, m.period), (0xab, 2, 2));
+    }
+
+    #[test]
+    fn test_to_base() {
+        assert_eq!(to_base(0, 10), "0");
+        assert_eq!(to_base(255, 16), "ff");
+        assert_eq!(to_base(5, 2), "101");
+        assert_eq!(to_base(35, 36), "z");
+    }
+
+    #[test]
+    fn test_matches_in_agree_with_sums() {
+        for (start, end) in [(1, 1500), (99_990, 1_001_500)] {
+            let repeat = matches_in(start, end, 10, false);
+            let twice = matches_in(start, end, 10, true);
+
+            let repeat_sum: u128 = repeat.iter().map(|m| m.id as u128).sum();
+            let twice_sum: u128 = twice.iter().map(|m| m.id as u128).sum();
+
+            assert_eq!(repeat_sum, sum_repeat(start, end, 10));
+            assert_eq!(twice_sum, sum_twice(start, end, 10));
+            assert!(repeat.windows(2).all(|w| w[0].id < w[1].id));
+        }
+    }
+
+    #[test]
+    fn test_mode_list() {
+        let input = vec!["95-115,1111-1111".to_string()];
+        let out = Day2::mode(&input, "list", &Params::default()).unwrap();
+        assert_eq!(
+            out,
+            "95-115:\n  99 = 9 x 2\n  111 = 1 x 3\n1111-1111:\n  1111 = 1 x 4\nperiod histogram:\n  1: 3"
+        );
+
+        let part_a = Params::from_strs(&["part=a"]);
+        let out = Day2::mode(&input, "list", &part_a).unwrap();
+        assert_eq!(
+            out,
+            "95-115:\n  99 = 9 x 2\n1111-1111:\n  1111 = 1 x 4\nperiod histogram:\n  1: 2"
+        );
+    }
+
+    #[test]
+    fn test_merge_ranges() {
+        assert_eq!(
+            merge_ranges(vec![(10, 20), (1, 5), (15, 30), (6, 8), (40, 40)]),
+            vec![(1, 8), (10, 30), (40, 40)]
+        );
+        assert_eq!(
+            merge_ranges(vec![(5, u64::MAX), (u64::MAX, u64::MAX)]),
+            vec![(5, u64::MAX)]
+        );
+    }
+
+    #[test]
+    fn test_parse_range_errors() {
+        assert!(parse_range("1-2", 10).is_ok());
+        assert!(parse_range("12", 10).is_err());
+        assert!(parse_range("5-3", 10).is_err());
+        assert!(parse_range("+5-7", 10).is_err());
+        assert!(parse_range(" 5-7", 10).is_err());
+        assert!(parse_range("5-", 10).is_err());
+        assert!(parse_range("1-2-3", 10).is_err());
+        assert!(parse_range("1-99999999999999999999", 10).is_err());
+        assert!(parse_range("1-a", 10).is_err());
+        assert!(parse_range("1-a", 16).is_ok());
+    }
+
+    #[test]
+    fn test_parse_ranges_reports_positions() {
+        let input = vec![
+            "1-2,x5-7,9-3".to_string(),
+            "".to_string(),
+            "4-5,,6".to_string(),
+        ];
+        let err = parse_ranges(&input, 10).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "Invalid ranges:\n\
+             line 1, column 5: `x5-7`: `x` isn't a base 10 digit\n\
+             line 1, column 10: `9-3`: start is greater than end\n\
+             line 3, column 6: `6`: expected `start-end`"
+        );
+    }
+
+    #[test]
+    fn test_overlapping_ranges_count_once() {
+        let input = vec!["11-22,15-33,22-22".to_string()];
+        // 11, 22 and 33 are each counted once
+        assert_eq!(Day2::part_a(&input), "66");
+        assert_eq!(Day2::part_b(&input), "66");
+    }
+
+    #[test]
+    fn test_split_ranges() {
+        assert_eq!(
+            split_ranges(&[(1, 10), (20, 21)], 10, 4, 8),
+            vec![(1, 3), (4, 6), (7, 9), (10, 10), (20, 21)]
+        );
+        assert_eq!(
+            split_ranges(&[(1, 10), (20, 21)], 10, 4, 1),
+            vec![(1, 9), (10, 10), (20, 21)]
+        );
+        assert_eq!(
+            split_ranges(&[(u64::MAX - 2, u64::MAX)], 10, 2, 8),
+            vec![(u64::MAX - 2, u64::MAX - 1), (u64::MAX, u64::MAX)]
+        );
+    }
+
+    #[test]
+    fn test_split_ranges_is_bounded() {
+        // One piece per digit length, each split at most `max_chunks` ways
+        let chunks = split_ranges(&[(0, u64::MAX)], 10, 1, 4);
+        assert_eq!(chunks.len(), 20 * 4);
+        assert_eq!(chunks.first().unwrap().0, 0);
+        assert_eq!(chunks.last().unwrap().1, u64::MAX);
+        assert!(chunks.windows(2).all(|w| w[0].1 + 1 == w[1].0));
+    }
+
+    #[test]
+    fn test_digit_bands() {
+        assert_eq!(
+            digit_bands(5, 1234, 10),
+            vec![(5, 9), (10, 99), (100, 999), (1000, 1234)]
+        );
+        assert_eq!(digit_bands(0, 3, 2), vec![(0, 1), (2, 3)]);
+        assert_eq!(
+            digit_bands(u64::MAX - 1, u64::MAX, 2),
+            vec![(u64::MAX - 1, u64::MAX)]
+        );
+    }
+
+    #[test]
+    fn test_parts_huge_range() {
+        // 10^15 IDs, which must not be walked or split ID by ID
+        let input = vec!["1-1000000000000000".to_string()];
+        let expected_a = sum_twice(1, 1_000_000_000_000_000, 10).to_string();
+        let expected_b = sum_repeat(1, 1_000_000_000_000_000, 10).to_string();
+
+        for args in [vec![], vec!["chunk=1"], vec!["threads=1", "chunk=1"]] {
+            let params = Params::from_strs(&args);
+            assert_eq!(Day2::part_a_with(&input, &params).unwrap(), expected_a);
+            assert_eq!(Day2::part_b_with(&input, &params).unwrap(), expected_b);
+        }
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let inputs = [
+            (
+                vec![
+                    "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,".to_string(),
+                    "1698522-1698528,446443-446449,38593856-38593862,565653-565659".to_string(),
+                ],
+                10,
+            ),
+            (vec!["f0-1000,abc-fff0,1-ff".to_string()], 16),
+            (vec!["1-101010101,11-1111".to_string()], 2),
+        ];
+
+        for (input, base) in inputs {
+            let with = |extra: &[String]| {
+                let mut args = vec![format!("base={}", base)];
+                args.extend_from_slice(extra);
+                Params::parse(&args).unwrap()
+            };
+
+            let sequential = with(&["threads=1".to_string(), "chunk=1".to_string()]);
+            let expected_a = Day2::part_a_with(&input, &sequential).unwrap();
+            let expected_b = Day2::part_b_with(&input, &sequential).unwrap();
+
+            for chunk in [1, 7, 1000, CHUNK_SIZE] {
+                for threads in [0, 1, 3] {
+                    let p = with(&[format!("threads={}", threads), format!("chunk={}", chunk)]);
+                    assert_eq!(Day2::part_a_with(&input, &p).unwrap(), expected_a);
+                    assert_eq!(Day2::part_b_with(&input, &p).unwrap(), expected_b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_chunk() {
+        let input = vec!["11-22".to_string()];
+        let params = Params::from_strs(&["chunk=0"]);
+        assert!(Day2::part_a_with(&input, &params).is_err());
     }
 }
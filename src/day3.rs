@@ -4,8 +4,33 @@
 //! by greedily selecting the largest available digit at each position while
 //! ensuring enough digits remain for subsequent positions.
 //!
+//! The selection runs in a single pass with a monotonic stack, so it is O(n)
+//! in the bank length regardless of how many digits are extracted.
+//!
 //! ## Input Format
-//! Each line contains a sequence of single digits (0-9).
+//! Each line contains a sequence of single digits (0-9), at least as many
+//! as the number of digits to extract. Blank lines are ignored.
+//!
+//! ## Parameters
+//! * `digits` - Number of digits to extract from each bank, overriding the
+//!   part's default. Joltages and their sum are exact at any length: they
+//!   use `u64`/`u128` arithmetic while it fits and arbitrary-precision
+//!   decimals beyond that.
+//! * `objective` - `largest` (the default) selects the largest number;
+//!   `smallest` selects the smallest one instead.
+//! * `no_leading_zero` - With `objective=smallest`, the first selected digit
+//!   must not be zero.
+//! * `max_gap` - With `objective=largest`, consecutive selected digits must
+//!   be at most this many positions apart.
+//!
+//! Whatever the objective, [`Objective::select`] returns the positions in
+//! the bank of the chosen digits, which the `highlight` mode shows.
+//!
+//! ## Modes
+//! * `highlight` - Prints each bank with its selected digits highlighted,
+//!   followed by its joltage, then the total. `style=ansi` (the default)
+//!   colours the digits; `style=brackets` wraps them in `[]`. `part=a|b`
+//!   picks the default digit count (defaults to `b`).
 //!
 //! ## Part A
 //! Extracts a 2-digit joltage value from each line and sums them.
@@ -13,41 +38,325 @@
 //! ## Part B
 //! Extracts a 12-digit joltage value from each line and sums them.
 
+mod decimal;
+mod objective;
+
+use anyhow::{Result, bail};
+
 use crate::day::Day;
+use crate::params::Params;
+use decimal::Decimal;
+use objective::Objective;
+
+/// Most digits whose value always fits in a `u64`.
+const U64_DIGITS: usize = 19;
+
+/// Most digits whose value always fits in a `u128`.
+const U128_DIGITS: usize = 38;
 
 /// Solution for Day 3: Joltage Extraction puzzle.
 pub struct Day3 {}
 
+/// How the `highlight` mode marks the selected digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Highlight {
+    /// Bold green ANSI colour
+    Ansi,
+    /// Square brackets around each digit
+    Brackets,
+}
+
 impl Day for Day3 {
     fn part_a(lines: &[String]) -> String {
-        lines
-            .iter()
-            .map(|l| {
-                l.chars()
-                    .map(|c| (c as u64) - ('0' as u64))
-                    .collect::<Vec<_>>()
-            })
-            .map(|b| get_joltage(&b, 2))
-            .sum::<u64>()
-            .to_string()
+        Day3::part_a_with(lines, &Params::default()).unwrap()
     }
 
     fn part_b(lines: &[String]) -> String {
-        lines
+        Day3::part_b_with(lines, &Params::default()).unwrap()
+    }
+
+    fn part_a_with(lines: &[String], params: &Params) -> Result<String> {
+        let digits_to_find = params.get_or("digits", 2)?;
+        let objective = Objective::from_params(params)?;
+
+        total_joltage(
+            &parse_banks(lines, digits_to_find)?,
+            digits_to_find,
+            objective,
+        )
+    }
+
+    fn part_b_with(lines: &[String], params: &Params) -> Result<String> {
+        let digits_to_find = params.get_or("digits", 12)?;
+        let objective = Objective::from_params(params)?;
+
+        total_joltage(
+            &parse_banks(lines, digits_to_find)?,
+            digits_to_find,
+            objective,
+        )
+    }
+
+    fn mode(lines: &[String], mode: &str, params: &Params) -> Result<String> {
+        match mode {
+            "highlight" => {
+                let part: String = params.get_or("part", "b".to_string())?;
+                let style: String = params.get_or("style", "ansi".to_string())?;
+
+                let default_digits = match part.as_str() {
+                    "a" => 2,
+                    "b" => 12,
+                    _ => bail!("Part [{}] must be a or b", part),
+                };
+
+                let style = match style.as_str() {
+                    "ansi" => Highlight::Ansi,
+                    "brackets" => Highlight::Brackets,
+                    _ => bail!("Style [{}] must be ansi or brackets", style),
+                };
+
+                let digits_to_find = params.get_or("digits", default_digits)?;
+                let objective = Objective::from_params(params)?;
+
+                highlight_banks(
+                    &parse_banks(lines, digits_to_find)?,
+                    digits_to_find,
+                    objective,
+                    style,
+                )
+            }
+            _ => bail!("Mode [{}] isn't supported by day3", mode),
+        }
+    }
+}
+
+/// Parses each line into a bank of digits.
+///
+/// Blank lines are skipped. Every character that isn't a decimal digit is
+/// reported with its line and column, as is every bank too short to supply
+/// `digits_to_find` digits.
+fn parse_banks(lines: &[String], digits_to_find: usize) -> Result<Vec<Vec<u64>>> {
+    let mut banks = vec![];
+    let mut errors = vec![];
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut bank = vec![];
+
+        for (j, c) in line.chars().enumerate() {
+            match c.to_digit(10) {
+                Some(digit) => bank.push(digit as u64),
+                None => errors.push(format!(
+                    "line {}, column {}: `{}` isn't a digit",
+                    i + 1,
+                    j + 1,
+                    c
+                )),
+            }
+        }
+
+        if line.chars().count() < digits_to_find {
+            errors.push(format!(
+                "line {}: bank has {} digits but {} are needed",
+                i + 1,
+                line.chars().count(),
+                digits_to_find
+            ));
+        }
+
+        banks.push(bank);
+    }
+
+    if !errors.is_empty() {
+        bail!("Invalid banks:\n{}", errors.join("\n"));
+    }
+
+    Ok(banks)
+}
+
+/// Prints every bank with its selected digits highlighted, followed by the
+/// bank's joltage and finally the total.
+///
+/// # Arguments
+/// * `banks` - The banks of digits, one per input line
+/// * `digits_to_find` - Number of digits to extract from each bank
+/// * `objective` - Which digits to select
+/// * `style` - How to mark the selected digits
+fn highlight_banks(
+    banks: &[Vec<u64>],
+    digits_to_find: usize,
+    objective: Objective,
+    style: Highlight,
+) -> Result<String> {
+    let mut out = vec![];
+
+    for bank in banks {
+        let indices = objective.select(bank, digits_to_find)?;
+        let mut selected = indices.iter().peekable();
+        let mut line = String::new();
+
+        for (i, digit) in bank.iter().enumerate() {
+            if selected.next_if_eq(&&i).is_none() {
+                line.push_str(&digit.to_string());
+                continue;
+            }
+
+            match style {
+                Highlight::Ansi => line.push_str(&format!("\x1b[1;32m{}\x1b[0m", digit)),
+                Highlight::Brackets => line.push_str(&format!("[{}]", digit)),
+            }
+        }
+
+        let digits: Vec<u64> = indices.iter().map(|&i| bank[i]).collect();
+        out.push(format!("{} {}", line, Decimal::from_digits(&digits)));
+    }
+
+    out.push(format!(
+        "total: {}",
+        total_joltage(banks, digits_to_find, objective)?
+    ));
+
+    Ok(out.join("\n"))
+}
+
+/// Sums the joltage of every bank exactly, whatever the digit count.
+///
+/// Up to [`U64_DIGITS`] digits each joltage is a `u64`, and up to
+/// [`U128_DIGITS`] a `u128`; either way the sum is accumulated in a `u128`.
+/// Longer joltages, or sums that overflow a `u128`, fall back to
+/// arbitrary-precision [`Decimal`] arithmetic.
+///
+/// # Arguments
+/// * `banks` - The banks of digits, one per input line
+/// * `digits_to_find` - Number of digits to extract from each bank
+/// * `objective` - Which digits to select
+///
+/// # Returns
+/// The exact sum in decimal, or an error if a bank has no valid selection.
+fn total_joltage(
+    banks: &[Vec<u64>],
+    digits_to_find: usize,
+    objective: Objective,
+) -> Result<String> {
+    let joltages = banks
+        .iter()
+        .map(|b| {
+            let indices = objective.select(b, digits_to_find)?;
+            Ok(indices.iter().map(|&i| b[i]).collect())
+        })
+        .collect::<Result<Vec<Vec<u64>>>>()?;
+
+    let total = if digits_to_find <= U64_DIGITS {
+        joltages
             .iter()
-            .map(|l| {
-                l.chars()
-                    .map(|c| (c as u64) - ('0' as u64))
-                    .collect::<Vec<_>>()
-            })
-            .map(|b| get_joltage(&b, 12))
-            .sum::<u64>()
-            .to_string()
+            .try_fold(0u128, |acc, j| acc.checked_add(to_u64(j) as u128))
+    } else if digits_to_find <= U128_DIGITS {
+        joltages
+            .iter()
+            .try_fold(0u128, |acc, j| acc.checked_add(to_u128(j)))
+    } else {
+        None
+    };
+
+    if let Some(total) = total {
+        return Ok(total.to_string());
+    }
+
+    let mut total = Decimal::default();
+
+    for joltage in &joltages {
+        total += &Decimal::from_digits(joltage);
+    }
+
+    Ok(total.to_string())
+}
+
+/// Combines up to [`U64_DIGITS`] digits into a single number.
+fn to_u64(digits: &[u64]) -> u64 {
+    debug_assert!(digits.len() <= U64_DIGITS);
+
+    digits.iter().fold(0, |acc, val| acc * 10 + val)
+}
+
+/// Combines up to [`U128_DIGITS`] digits into a single number.
+fn to_u128(digits: &[u64]) -> u128 {
+    debug_assert!(digits.len() <= U128_DIGITS);
+
+    digits.iter().fold(0, |acc, &val| acc * 10 + val as u128)
+}
+
+/// Extracts the maximum joltage value of up to [`U64_DIGITS`] digits.
+///
+/// This is the puzzle's rule, [`Objective::Largest`], on its own, and is
+/// only kept as a reference for tests. Everywhere else
+/// [`Objective::select`] replaces it: it exposes the chosen positions for
+/// any objective, and with [`Objective::Largest`] picks the same ones.
+///
+/// # Arguments
+/// * `bank` - Slice of available digits (0-9)
+/// * `digits_to_find` - Number of digits to extract
+///
+/// # Returns
+/// A tuple of (selected_indices, joltage), where the indices are the
+/// positions in `bank` of the chosen digits, in order.
+#[cfg(test)]
+fn get_joltage(bank: &[u64], digits_to_find: usize) -> (Vec<usize>, u64) {
+    let indices = select_indices(bank, digits_to_find);
+    let joltage = to_u64(&indices.iter().map(|&i| bank[i]).collect::<Vec<_>>());
+
+    (indices, joltage)
+}
+
+/// Selects the positions of the maximum joltage value with a monotonic stack.
+///
+/// This is the engine behind [`Objective::Largest`]; callers go through
+/// [`Objective::select`].
+///
+/// Digits are pushed in order; before each push, smaller digits are popped
+/// off the stack as long as enough digits remain to still fill every
+/// position. The stack then holds the lexicographically largest subsequence
+/// of length `digits_to_find`, the same as a windowed greedy search for the
+/// maximum digit at each position, but each digit is pushed and popped at
+/// most once.
+///
+/// Among equal digits the earliest position is kept, so the indices match
+/// the windowed search's leftmost-maximum choice.
+///
+/// # Arguments
+/// * `bank` - Slice of available digits (0-9)
+/// * `digits_to_find` - Number of digits to extract
+///
+/// # Returns
+/// The indices of the selected digits in `bank`, in ascending order.
+fn select_indices(bank: &[u64], digits_to_find: usize) -> Vec<usize> {
+    let mut stack: Vec<usize> = Vec::with_capacity(digits_to_find);
+
+    for (i, &digit) in bank.iter().enumerate() {
+        let remaining = bank.len() - i;
+
+        while let Some(&top) = stack.last() {
+            if bank[top] >= digit || stack.len() + remaining <= digits_to_find {
+                break;
+            }
+            stack.pop();
+        }
+
+        if stack.len() < digits_to_find {
+            stack.push(i);
+        }
     }
+
+    stack
 }
 
 /// Extracts the maximum joltage value by greedily selecting digits.
 ///
+/// This is the original O(n·k) search, kept as a reference for
+/// [`get_joltage`].
+///
 /// For each digit position, finds the maximum digit in the valid range
 /// (leaving enough remaining digits for subsequent positions), then
 /// continues searching from after that position.
@@ -57,8 +366,10 @@ impl Day for Day3 {
 /// * `digits_to_find` - Number of digits to extract
 ///
 /// # Returns
-/// The extracted digits combined into a single number.
-fn get_joltage(bank: &[u64], digits_to_find: usize) -> u64 {
+/// A tuple of (selected_indices, joltage).
+#[cfg(test)]
+fn get_joltage_windowed(bank: &[u64], digits_to_find: usize) -> (Vec<usize>, u64) {
+    let mut indices = vec![];
     let mut digits = vec![];
 
     let mut idx = 0;
@@ -73,10 +384,11 @@ fn get_joltage(bank: &[u64], digits_to_find: usize) -> u64 {
 
         idx = i + 1;
 
+        indices.push(i);
         digits.push(new_digit);
     }
 
-    digits.iter().fold(0, |acc, val| (acc * 10 + val))
+    (indices, digits.iter().fold(0, |acc, val| acc * 10 + val))
 }
 
 /// Finds the maximum value in a slice and returns its adjusted index.
@@ -87,13 +399,12 @@ fn get_joltage(bank: &[u64], digits_to_find: usize) -> u64 {
 ///
 /// # Returns
 /// A tuple of (adjusted_index, max_value).
+#[cfg(test)]
 fn max_of_range_with_idx(bank: &[u64], start: usize) -> (usize, u64) {
     let mut max_idx = 0;
     let mut max = bank[max_idx];
 
-    for i in max_idx + 1..bank.len() {
-        let val = bank[i];
-
+    for (i, &val) in bank.iter().enumerate().skip(1) {
         if val > max {
             max = val;
             max_idx = i;
@@ -154,7 +465,7 @@ mod tests {
         // Second: find max in [1,8] -> 8 at index 3
         // Result: 98
         let bank = vec![3, 9, 1, 8];
-        assert_eq!(get_joltage(&bank, 2), 98);
+        assert_eq!(get_joltage(&bank, 2).1, 98);
     }
 
     #[test]
@@ -168,21 +479,21 @@ mod tests {
         // end = 5 - (3 - 1 - 1) = 5 - 1 = 4, so bank[3..4] = [3], max=3 at idx 3
         // end = 5 - (3 - 2 - 1) = 5 - 0 = 5, so bank[4..5] = [7], max=7 at idx 4
         // Result: 937
-        assert_eq!(get_joltage(&bank, 3), 937);
+        assert_eq!(get_joltage(&bank, 3).1, 937);
     }
 
     #[test]
     fn test_get_joltage_all_digits() {
         let bank = vec![1, 2, 3];
         // Must select all 3 digits in order: 1, 2, 3 -> 123
-        assert_eq!(get_joltage(&bank, 3), 123);
+        assert_eq!(get_joltage(&bank, 3).1, 123);
     }
 
     #[test]
     fn test_get_joltage_single_digit() {
         let bank = vec![5, 3, 8, 1];
         // Find 1 digit: max of entire bank = 8
-        assert_eq!(get_joltage(&bank, 1), 8);
+        assert_eq!(get_joltage(&bank, 1).1, 8);
     }
 
     #[test]
@@ -192,7 +503,7 @@ mod tests {
         // First: max in [9,8,7] = 9 at idx 0
         // Second: max in [8,7,6] = 8 at idx 1
         // Result: 98
-        assert_eq!(get_joltage(&bank, 2), 98);
+        assert_eq!(get_joltage(&bank, 2).1, 98);
     }
 
     #[test]
@@ -236,4 +547,177 @@ mod tests {
         // All 5s, find 2 -> 55
         assert_eq!(Day3::part_a(&input), "55");
     }
+
+    /// Decodes `n` as a bank of `len` digits in base `digits`.
+    fn bank_from(mut n: u64, len: usize, digits: u64) -> Vec<u64> {
+        (0..len)
+            .map(|_| {
+                let d = n % digits;
+                n /= digits;
+                d
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_get_joltage_matches_windowed_exhaustive() {
+        // Every bank of up to 7 digits drawn from 0-3, for every k
+        for len in 1..=7 {
+            for n in 0..4u64.pow(len as u32) {
+                let bank = bank_from(n, len, 4);
+                for k in 1..=len {
+                    assert_eq!(
+                        get_joltage(&bank, k),
+                        get_joltage_windowed(&bank, k),
+                        "bank {:?}, k {}",
+                        bank,
+                        k
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_joltage_matches_windowed_random() {
+        // Pseudo-random long banks (xorshift) with every digit 0-9
+        let mut state: u64 = 0x2545f4914f6cdd1d;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..200 {
+            let len = (next() % 100 + 1) as usize;
+            let bank: Vec<u64> = (0..len).map(|_| next() % 10).collect();
+            let k = (next() as usize % len.min(19)) + 1;
+            assert_eq!(get_joltage(&bank, k), get_joltage_windowed(&bank, k));
+        }
+    }
+
+    #[test]
+    fn test_to_u128() {
+        let bank: Vec<u64> = (0..30).map(|i| 9 - i % 10).collect();
+        let indices = select_indices(&bank, 25);
+        let digits: Vec<u64> = indices.iter().map(|&i| bank[i]).collect();
+        assert_eq!(to_u128(&digits), 9876598765432109876543210);
+    }
+
+    #[test]
+    fn test_total_joltage_paths_agree() {
+        let banks = vec![vec![9; 50], vec![1; 50]];
+
+        // u64, u128 and decimal paths all give repeated 9s + repeated 1s
+        for k in [5, 19, 20, 38, 39, 50] {
+            assert_eq!(
+                total_joltage(&banks, k, Objective::Largest).unwrap(),
+                format!("1{}0", "1".repeat(k - 1))
+            );
+        }
+    }
+
+    #[test]
+    fn test_total_joltage_sum_overflows_u128() {
+        // Three 38-digit joltages of 9s overflow a u128 when summed
+        let banks = vec![vec![9; 38]; 3];
+        assert_eq!(
+            total_joltage(&banks, 38, Objective::Largest).unwrap(),
+            format!("2{}7", "9".repeat(37))
+        );
+    }
+
+    #[test]
+    fn test_part_b_with_long_digits() {
+        let input = vec!["9".repeat(30), "1".repeat(30)];
+        let params = Params::from_strs(&["digits=25"]);
+        assert_eq!(
+            Day3::part_b_with(&input, &params).unwrap(),
+            format!("1{}0", "1".repeat(24))
+        );
+    }
+
+    #[test]
+    fn test_get_joltage_indices() {
+        let bank = vec![3, 9, 1, 8, 8];
+        assert_eq!(get_joltage(&bank, 2), (vec![1, 3], 98));
+        assert_eq!(get_joltage(&bank, 3), (vec![1, 3, 4], 988));
+    }
+
+    #[test]
+    fn test_highlight_brackets() {
+        let input = vec!["3918".to_string(), "12345".to_string()];
+        let params = Params::from_strs(&["part=a", "style=brackets"]);
+        assert_eq!(
+            Day3::mode(&input, "highlight", &params).unwrap(),
+            "3[9]1[8] 98\n123[4][5] 45\ntotal: 143"
+        );
+    }
+
+    #[test]
+    fn test_highlight_ansi() {
+        let input = vec!["291".to_string()];
+        let params = Params::from_strs(&["digits=1"]);
+        assert_eq!(
+            Day3::mode(&input, "highlight", &params).unwrap(),
+            "2\x1b[1;32m9\x1b[0m1 9\ntotal: 9"
+        );
+    }
+
+    #[test]
+    fn test_highlight_invalid_style() {
+        let input = vec!["291".to_string()];
+        let params = Params::from_strs(&["style=bold"]);
+        assert!(Day3::mode(&input, "highlight", &params).is_err());
+    }
+
+    #[test]
+    fn test_parts_with_objectives() {
+        let input = vec!["3020051".to_string(), "9001".to_string()];
+
+        // 00 + 00
+        let smallest = Params::from_strs(&["objective=smallest"]);
+        assert_eq!(Day3::part_a_with(&input, &smallest).unwrap(), "0");
+
+        // 20 + 90
+        let no_zero = Params::from_strs(&["objective=smallest", "no_leading_zero"]);
+        assert_eq!(Day3::part_a_with(&input, &no_zero).unwrap(), "110");
+
+        // 51 + 90 (the 9 can't reach the 1)
+        let gap = Params::from_strs(&["max_gap=2", "digits=2"]);
+        assert_eq!(Day3::part_b_with(&input, &gap).unwrap(), "141");
+    }
+
+    #[test]
+    fn test_parse_banks() {
+        let input = vec!["3918".to_string(), "".to_string(), "07".to_string()];
+        assert_eq!(
+            parse_banks(&input, 2).unwrap(),
+            vec![vec![3, 9, 1, 8], vec![0, 7]]
+        );
+    }
+
+    #[test]
+    fn test_parse_banks_non_digits() {
+        let input = vec!["12 4".to_string(), "5x6-".to_string()];
+        assert_eq!(
+            parse_banks(&input, 2).unwrap_err().to_string(),
+            "Invalid banks:\n\
+             line 1, column 3: ` ` isn't a digit\n\
+             line 2, column 2: `x` isn't a digit\n\
+             line 2, column 4: `-` isn't a digit"
+        );
+    }
+
+    #[test]
+    fn test_parse_banks_too_short() {
+        let input = vec!["123456789012".to_string(), "98765".to_string()];
+        assert!(parse_banks(&input, 2).is_ok());
+        assert_eq!(
+            parse_banks(&input, 12).unwrap_err().to_string(),
+            "Invalid banks:\nline 2: bank has 5 digits but 12 are needed"
+        );
+        assert!(Day3::part_b_with(&input, &Params::default()).is_err());
+    }
 }
@@ -0,0 +1,104 @@
+//! Arbitrary-precision decimal numbers for joltages too long for a `u128`.
+//!
+//! Only what the joltage sums need is supported: building a number from its
+//! decimal digits, adding two numbers and printing the result.
+
+use std::fmt;
+use std::ops::AddAssign;
+
+/// A non-negative integer of any size, stored as decimal digits.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Decimal {
+    /// Digits from least to most significant, without leading zeros
+    digits: Vec<u8>,
+}
+
+impl Decimal {
+    /// Builds a number from its digits, most significant first.
+    ///
+    /// # Arguments
+    /// * `digits` - Decimal digits (0-9), most significant first
+    pub fn from_digits(digits: &[u64]) -> Self {
+        let mut num = Decimal {
+            digits: digits.iter().rev().map(|&d| d as u8).collect(),
+        };
+        num.trim();
+        num
+    }
+
+    /// Drops leading zeros so that zero is the empty digit list.
+    fn trim(&mut self) {
+        while self.digits.last() == Some(&0) {
+            self.digits.pop();
+        }
+    }
+}
+
+impl AddAssign<&Decimal> for Decimal {
+    fn add_assign(&mut self, other: &Decimal) {
+        if self.digits.len() < other.digits.len() {
+            self.digits.resize(other.digits.len(), 0);
+        }
+
+        let mut carry = 0;
+
+        for (i, digit) in self.digits.iter_mut().enumerate() {
+            let sum = *digit + other.digits.get(i).copied().unwrap_or(0) + carry;
+            *digit = sum % 10;
+            carry = sum / 10;
+        }
+
+        if carry > 0 {
+            self.digits.push(carry);
+        }
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.digits.is_empty() {
+            return write!(f, "0");
+        }
+
+        for digit in self.digits.iter().rev() {
+            write!(f, "{}", digit)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_digits() {
+        assert_eq!(Decimal::from_digits(&[1, 2, 3]).to_string(), "123");
+        assert_eq!(Decimal::from_digits(&[0, 0, 7]).to_string(), "7");
+        assert_eq!(Decimal::from_digits(&[0, 0]).to_string(), "0");
+        assert_eq!(Decimal::from_digits(&[]).to_string(), "0");
+    }
+
+    #[test]
+    fn test_add_carries() {
+        let mut sum = Decimal::from_digits(&[9, 9, 9]);
+        sum += &Decimal::from_digits(&[1]);
+        assert_eq!(sum.to_string(), "1000");
+
+        let mut sum = Decimal::from_digits(&[5]);
+        sum += &Decimal::from_digits(&[9, 9, 5]);
+        assert_eq!(sum.to_string(), "1000");
+    }
+
+    #[test]
+    fn test_add_beyond_u128() {
+        let max = u128::MAX.to_string();
+        let digits: Vec<u64> = max.bytes().map(|b| (b - b'0') as u64).collect();
+
+        let mut sum = Decimal::from_digits(&digits);
+        sum += &Decimal::from_digits(&digits);
+
+        assert_eq!(sum.to_string(), "680564733841876926926749214863536422910");
+    }
+}
@@ -0,0 +1,391 @@
+//! Selection objectives for choosing which digits of a bank form its joltage.
+//!
+//! The puzzle always takes the largest `k`-digit subsequence, but variants
+//! may instead want the smallest one, or the largest one whose chosen digits
+//! are never more than a given distance apart.
+
+use anyhow::{Result, bail};
+
+use super::select_indices;
+use crate::params::Params;
+
+/// What a selection of `k` digits from a bank should optimise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    /// The lexicographically largest subsequence (the puzzle's rule)
+    Largest,
+    /// The lexicographically smallest subsequence, optionally forbidding a
+    /// leading zero
+    Smallest { no_leading_zero: bool },
+    /// The largest subsequence whose consecutive chosen indices differ by at
+    /// most `max_gap`
+    LargestWithGap { max_gap: usize },
+}
+
+impl Objective {
+    /// Reads the objective from the `objective`, `no_leading_zero` and
+    /// `max_gap` parameters, defaulting to [`Objective::Largest`].
+    pub fn from_params(params: &Params) -> Result<Self> {
+        let objective: String = params.get_or("objective", "largest".to_string())?;
+        let no_leading_zero: Option<bool> = params.get("no_leading_zero")?;
+        let max_gap: Option<usize> = params.get("max_gap")?;
+
+        match objective.as_str() {
+            "largest" => {
+                if no_leading_zero.is_some() {
+                    bail!("Parameter [no_leading_zero] only applies to objective=smallest");
+                }
+
+                match max_gap {
+                    None => Ok(Objective::Largest),
+                    Some(0) => bail!("Parameter [max_gap] must be at least 1"),
+                    Some(max_gap) => Ok(Objective::LargestWithGap { max_gap }),
+                }
+            }
+            "smallest" => {
+                if max_gap.is_some() {
+                    bail!("Parameter [max_gap] only applies to objective=largest");
+                }
+
+                Ok(Objective::Smallest {
+                    no_leading_zero: no_leading_zero.unwrap_or(false),
+                })
+            }
+            _ => bail!("Objective [{}] must be largest or smallest", objective),
+        }
+    }
+
+    /// Selects `digits_to_find` digits from the bank according to the
+    /// objective.
+    ///
+    /// This is how to find which positions of a bank make up its joltage;
+    /// the joltage itself is the selected digits in order.
+    ///
+    /// # Arguments
+    /// * `bank` - Slice of available digits (0-9)
+    /// * `digits_to_find` - Number of digits to select
+    ///
+    /// # Returns
+    /// The indices of the selected digits in ascending order, or an error if
+    /// no selection satisfies the objective.
+    pub fn select(&self, bank: &[u64], digits_to_find: usize) -> Result<Vec<usize>> {
+        if digits_to_find > bank.len() {
+            bail!(
+                "Can't select {} digits from a bank of {}",
+                digits_to_find,
+                bank.len()
+            );
+        }
+
+        match *self {
+            Objective::Largest => Ok(select_indices(bank, digits_to_find)),
+            Objective::Smallest { no_leading_zero } => {
+                select_smallest(bank, digits_to_find, no_leading_zero)
+            }
+            Objective::LargestWithGap { max_gap } => {
+                Ok(select_largest_with_gap(bank, digits_to_find, max_gap))
+            }
+        }
+    }
+}
+
+/// Selects the lexicographically smallest subsequence.
+///
+/// Mirrors [`select_indices`]: a monotonic stack, popping larger digits
+/// instead of smaller ones. With `no_leading_zero`, the first digit is the
+/// leftmost smallest non-zero digit that still leaves room for the rest, and
+/// the stack fills in the remaining digits after it.
+fn select_smallest(
+    bank: &[u64],
+    digits_to_find: usize,
+    no_leading_zero: bool,
+) -> Result<Vec<usize>> {
+    if !no_leading_zero || digits_to_find == 0 {
+        return Ok(smallest_stack(bank, digits_to_find, 0));
+    }
+
+    let Some(first) = (0..=bank.len() - digits_to_find)
+        .filter(|&i| bank[i] != 0)
+        .min_by_key(|&i| bank[i])
+    else {
+        bail!(
+            "No non-zero digit can lead a {}-digit selection",
+            digits_to_find
+        );
+    };
+
+    let mut indices = vec![first];
+    indices.extend(smallest_stack(
+        &bank[first + 1..],
+        digits_to_find - 1,
+        first + 1,
+    ));
+
+    Ok(indices)
+}
+
+/// Runs the monotonic stack for the smallest subsequence, offsetting the
+/// returned indices by `start`.
+fn smallest_stack(bank: &[u64], digits_to_find: usize, start: usize) -> Vec<usize> {
+    let mut stack: Vec<usize> = Vec::with_capacity(digits_to_find);
+
+    for (i, &digit) in bank.iter().enumerate() {
+        let remaining = bank.len() - i;
+
+        while let Some(&top) = stack.last() {
+            if bank[top] <= digit || stack.len() + remaining <= digits_to_find {
+                break;
+            }
+            stack.pop();
+        }
+
+        if stack.len() < digits_to_find {
+            stack.push(i);
+        }
+    }
+
+    stack.into_iter().map(|i| i + start).collect()
+}
+
+/// Selects the largest subsequence whose consecutive indices are at most
+/// `max_gap` apart.
+///
+/// A greedy choice no longer works: of two equal digits, the later one may
+/// reach better digits further right. Instead, working up from one digit
+/// to `digits_to_find`, every start position is ranked by the best
+/// subsequence beginning there: its own digit, then the best-ranked start
+/// of one digit fewer within `max_gap`. Equal subsequences keep the
+/// leftmost start. This is O(n·k·max_gap) plus sorting each level.
+///
+/// Taking consecutive digits is always allowed, so any position with
+/// enough digits after it can start a selection.
+fn select_largest_with_gap(bank: &[u64], digits_to_find: usize, max_gap: usize) -> Vec<usize> {
+    let n = bank.len();
+
+    if digits_to_find == 0 {
+        return vec![];
+    }
+
+    // Level 1: a single digit ranks by its value
+    let mut rank: Vec<usize> = bank.iter().map(|&d| d as usize).collect();
+    let mut next: Vec<Vec<usize>> = vec![];
+
+    for level in 2..=digits_to_find {
+        // Positions that can start `level` digits
+        let starts = n + 1 - level;
+        let mut best_next = Vec::with_capacity(starts);
+
+        for i in 0..starts {
+            // Candidates must still start `level - 1` digits themselves
+            let last = (i + max_gap).min(n - (level - 1));
+            let best = (i + 1..=last).rev().max_by_key(|&j| rank[j]).unwrap();
+            best_next.push(best);
+        }
+
+        let keys: Vec<(u64, usize)> = (0..starts).map(|i| (bank[i], rank[best_next[i]])).collect();
+
+        let mut sorted = keys.clone();
+        sorted.sort();
+        sorted.dedup();
+
+        rank = keys
+            .iter()
+            .map(|key| sorted.binary_search(key).unwrap())
+            .collect();
+        next.push(best_next);
+    }
+
+    // `rev` so that `max_by_key`, which keeps the last maximum, picks the
+    // leftmost position
+    let mut idx = (0..=n - digits_to_find)
+        .rev()
+        .max_by_key(|&i| rank[i])
+        .unwrap();
+    let mut indices = vec![idx];
+
+    for best_next in next.iter().rev() {
+        idx = best_next[idx];
+        indices.push(idx);
+    }
+
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tries every `k`-subset of positions, keeping the best by `better`.
+    fn brute_force(
+        bank: &[u64],
+        k: usize,
+        allowed: impl Fn(&[usize]) -> bool,
+        better: impl Fn(&[u64], &[u64]) -> bool,
+    ) -> Option<Vec<usize>> {
+        let n = bank.len();
+        let mut best: Option<(Vec<usize>, Vec<u64>)> = None;
+
+        for mask in 0u32..1 << n {
+            if mask.count_ones() as usize != k {
+                continue;
+            }
+
+            let indices: Vec<usize> = (0..n).filter(|i| mask & (1 << i) != 0).collect();
+            if !allowed(&indices) {
+                continue;
+            }
+
+            let digits: Vec<u64> = indices.iter().map(|&i| bank[i]).collect();
+            if best.as_ref().is_none_or(|(_, b)| better(&digits, b)) {
+                best = Some((indices, digits));
+            }
+        }
+
+        best.map(|(indices, _)| indices)
+    }
+
+    fn digits_of(bank: &[u64], indices: &[usize]) -> Vec<u64> {
+        indices.iter().map(|&i| bank[i]).collect()
+    }
+
+    fn banks() -> impl Iterator<Item = Vec<u64>> {
+        // Every bank of up to 7 digits drawn from 0-2
+        (1..=7).flat_map(|len| {
+            (0..3u64.pow(len as u32)).map(move |mut n| {
+                (0..len)
+                    .map(|_| {
+                        let d = n % 3;
+                        n /= 3;
+                        d
+                    })
+                    .collect()
+            })
+        })
+    }
+
+    #[test]
+    fn test_smallest() {
+        let bank = vec![3, 0, 2, 0, 5, 1];
+        let objective = Objective::Smallest {
+            no_leading_zero: false,
+        };
+        assert_eq!(objective.select(&bank, 3).unwrap(), vec![1, 3, 5]);
+    }
+
+    #[test]
+    fn test_smallest_no_leading_zero() {
+        let bank = vec![3, 0, 2, 0, 5, 1];
+        let objective = Objective::Smallest {
+            no_leading_zero: true,
+        };
+        assert_eq!(objective.select(&bank, 3).unwrap(), vec![2, 3, 5]);
+
+        let zeros = vec![0, 0, 1];
+        assert!(objective.select(&zeros, 2).is_err());
+    }
+
+    #[test]
+    fn test_smallest_matches_brute_force() {
+        for bank in banks() {
+            for k in 1..=bank.len() {
+                for no_leading_zero in [false, true] {
+                    let expected = brute_force(
+                        &bank,
+                        k,
+                        |idx| !no_leading_zero || bank[idx[0]] != 0,
+                        |a, b| a < b,
+                    );
+                    let actual = Objective::Smallest { no_leading_zero }
+                        .select(&bank, k)
+                        .ok();
+
+                    assert_eq!(
+                        actual.map(|i| digits_of(&bank, &i)),
+                        expected.map(|i| digits_of(&bank, &i)),
+                        "bank {:?}, k {}",
+                        bank,
+                        k
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_largest_with_gap() {
+        // Greedy would take the first 9 and be stuck with a 0 next
+        let bank = vec![9, 0, 0, 9, 5];
+        let objective = Objective::LargestWithGap { max_gap: 2 };
+        assert_eq!(objective.select(&bank, 2).unwrap(), vec![3, 4]);
+    }
+
+    #[test]
+    fn test_largest_with_gap_matches_brute_force() {
+        for bank in banks() {
+            for k in 1..=bank.len() {
+                for max_gap in 1..=3 {
+                    let expected = brute_force(
+                        &bank,
+                        k,
+                        |idx| idx.windows(2).all(|w| w[1] - w[0] <= max_gap),
+                        |a, b| a > b,
+                    )
+                    .unwrap();
+                    let actual = Objective::LargestWithGap { max_gap }
+                        .select(&bank, k)
+                        .unwrap();
+
+                    assert!(actual.windows(2).all(|w| w[1] - w[0] <= max_gap));
+                    assert_eq!(
+                        digits_of(&bank, &actual),
+                        digits_of(&bank, &expected),
+                        "bank {:?}, k {}, max_gap {}",
+                        bank,
+                        k,
+                        max_gap
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_gap_matches_largest() {
+        for bank in banks() {
+            for k in 1..=bank.len() {
+                assert_eq!(
+                    Objective::LargestWithGap { max_gap: 7 }
+                        .select(&bank, k)
+                        .unwrap(),
+                    Objective::Largest.select(&bank, k).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_params() {
+        let parse = |args: &[&str]| Objective::from_params(&Params::from_strs(args));
+
+        assert_eq!(parse(&[]).unwrap(), Objective::Largest);
+        assert_eq!(
+            parse(&["max_gap=3"]).unwrap(),
+            Objective::LargestWithGap { max_gap: 3 }
+        );
+        assert_eq!(
+            parse(&["objective=smallest", "no_leading_zero"]).unwrap(),
+            Objective::Smallest {
+                no_leading_zero: true
+            }
+        );
+        assert!(parse(&["max_gap=0"]).is_err());
+        assert!(parse(&["objective=smallest", "max_gap=2"]).is_err());
+        assert!(parse(&["no_leading_zero"]).is_err());
+        assert!(parse(&["objective=median"]).is_err());
+    }
+
+    #[test]
+    fn test_too_few_digits() {
+        assert!(Objective::Largest.select(&[1, 2], 3).is_err());
+    }
+}
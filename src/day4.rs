@@ -6,20 +6,92 @@
 //! ## Input Format
 //! A grid where `.` represents empty space and `@` represents a bale.
 //!
+//! A stacked pile is given as several equally sized grids separated by
+//! blank lines, bottom layer first. Bales in a stack have the 26 squares
+//! around them in 3D as neighbours, and the neighbourhood parameters and
+//! modes don't apply.
+//!
+//! ## Parameters
+//! * `limit` - Bales with fewer neighbouring bales than this are
+//!   accessible; defaults to 4.
+//! * `neighbourhood` - Which squares are neighbours: `moore` (the default,
+//!   all squares within `radius`), `von-neumann` (squares within `radius`
+//!   orthogonal steps) or `hex` (6 neighbours, with odd rows shifted right).
+//! * `radius` - Radius of the `moore` or `von-neumann` neighbourhood;
+//!   defaults to 1.
+//! * `wrap` - Wrap around at the grid's edges like a torus instead of
+//!   treating everything beyond them as empty. Hexagonal grids need an even
+//!   number of rows to wrap.
+//!
+//! ## Modes
+//! Each mode shows the waves of part B's removal: wave 1 is every bale
+//! accessible at the start, wave 2 those accessible once wave 1 is gone,
+//! and so on.
+//! * `waves` - Prints the grid with each removed bale's wave number; bales
+//!   never removed stay `@`.
+//! * `animate` - Plays the removal in the terminal with ANSI colours,
+//!   showing each wave for `delay` milliseconds (default 100).
+//! * `frames` - Writes one PPM image per wave, `frame_0000.ppm` onwards,
+//!   into the directory `dir`, drawing each square as `scale` pixels
+//!   square (default 4).
+//! * `components` - Lists the connected components of bales, joined
+//!   through the 4 orthogonal squares or all 8 around them as set by
+//!   `connectivity` (default 8), with each one's size and bounding box,
+//!   then how many components and bales remain after each wave.
+//! * `bench` - Ignores the input and times both parts on a generated
+//!   `size` by `size` grid (default 10,000) where `density` percent of the
+//!   squares hold bales (default 70), comparing the bit-packed grid against
+//!   the square-by-square one. `seed` varies the generated grid. A grid of
+//!   two-row strips, which takes thousands of waves to clear, is timed too.
+//! * `life` - Runs the grid as a life-like cellular automaton with the
+//!   birth/survival `rule` (default `B3/S23`; part B's removal is
+//!   `B/S45678`) over the chosen neighbourhood, for up to `generations`
+//!   generations (default 1000). Stops early at a fixed point or cycle and
+//!   reports its length.
+//!
 //! ## Part A
-//! Counts bales that have fewer than 4 neighboring bales (accessible bales).
+//! Counts bales that have fewer than `limit` neighboring bales (accessible
+//! bales).
 //!
 //! ## Part B
 //! Iteratively removes accessible bales until no more can be removed,
 //! counting the total number of bales removed across all iterations.
+//! Neighbour counts are kept up to date as bales are removed, so only bales
+//! next to a removal are re-examined instead of rescanning the grid.
+//!
+//! With the puzzle's neighbourhood, both parts run on a bit-packed grid that
+//! counts the neighbours of 64 squares at once; other neighbourhoods use the
+//! square-by-square grid.
+
+mod automaton;
+mod bits;
+mod components;
+mod layers;
+mod neighbourhood;
+mod waves;
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Result, bail};
 
 use crate::day::Day;
+use crate::params::Params;
+use automaton::{Automaton, Rule};
+use bits::BitGrid;
+use components::Connectivity;
+use neighbourhood::Neighbourhood;
+
+/// Bales with fewer neighbours than this are accessible, unless the
+/// `limit` parameter says otherwise.
+const BALE_LIMIT: usize = 4;
 
 /// Solution for Day 4: Bale Accessibility puzzle.
 pub struct Day4 {}
 
 /// Represents a cell in the grid.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Square {
     /// Empty space (`.`)
     Empty,
@@ -29,92 +101,320 @@ enum Square {
 
 impl Day for Day4 {
     fn part_a(lines: &[String]) -> String {
-        let grid: Vec<Vec<_>> = lines
-            .iter()
-            .map(|l| {
-                l.chars()
-                    .map(|c| match c {
-                        '.' => Square::Empty,
-                        '@' => Square::Bale,
-                        _ => panic!(),
-                    })
-                    .collect()
-            })
-            .collect();
+        Day4::part_a_with(lines, &Params::default()).unwrap()
+    }
+
+    fn part_b(lines: &[String]) -> String {
+        Day4::part_b_with(lines, &Params::default()).unwrap()
+    }
+
+    fn part_a_with(lines: &[String], params: &Params) -> Result<String> {
+        let bale_limit = params.get_or("limit", BALE_LIMIT)?;
+
+        if let Some(stack) = parse_stack(lines, params)? {
+            return Ok(layers::accessible_count(&stack, bale_limit).to_string());
+        }
+
+        let grid = parse_flat(lines)?;
+        let neighbourhood = neighbourhood_for(&grid, params)?;
 
         let height = grid.len();
         let width = grid[0].len();
 
+        if neighbourhood == Neighbourhood::default() {
+            return Ok(BitGrid::new(&grid)?
+                .accessible_count(bale_limit)
+                .to_string());
+        }
+
         let mut accessable_coords = 0;
 
         for i in 0..height {
             for j in 0..width {
-                if is_accessable(i as isize, j as isize, &grid, 4) {
+                if is_accessable(i as isize, j as isize, &grid, bale_limit, &neighbourhood) {
                     accessable_coords += 1;
                 }
             }
         }
 
-        accessable_coords.to_string()
+        Ok(accessable_coords.to_string())
     }
 
-    fn part_b(lines: &[String]) -> String {
-        let mut grid: Vec<Vec<_>> = lines
-            .iter()
-            .map(|l| {
-                l.chars()
-                    .map(|c| match c {
-                        '.' => Square::Empty,
-                        '@' => Square::Bale,
-                        _ => panic!(),
-                    })
-                    .collect()
-            })
-            .collect();
+    fn part_b_with(lines: &[String], params: &Params) -> Result<String> {
+        let bale_limit = params.get_or("limit", BALE_LIMIT)?;
 
-        let height = grid.len();
-        let width = grid[0].len();
+        if let Some(mut stack) = parse_stack(lines, params)? {
+            return Ok(layers::remove_accessible(&mut stack, bale_limit).to_string());
+        }
 
-        let mut accessable_coords = 0;
+        let mut grid = parse_flat(lines)?;
+        let neighbourhood = neighbourhood_for(&grid, params)?;
 
-        loop {
-            let mut bales_to_remove = vec![];
+        if neighbourhood == Neighbourhood::default() {
+            let removed = BitGrid::new(&grid)?.remove_accessible(bale_limit);
+            return Ok(removed.to_string());
+        }
 
-            for i in 0..height {
-                for j in 0..width {
-                    if is_accessable(i as isize, j as isize, &grid, 4) {
-                        accessable_coords += 1;
-                        bales_to_remove.push((i, j));
-                    }
-                }
+        let waves = remove_accessable(&mut grid, bale_limit, &neighbourhood);
+
+        Ok(removed_count(&waves).to_string())
+    }
+
+    fn mode(lines: &[String], mode: &str, params: &Params) -> Result<String> {
+        if mode == "bench" {
+            let size = params.get_or("size", 10_000)?;
+            let density = params.get_or("density", 70)?;
+            let seed = params.get_or("seed", 1)?;
+
+            return bits::benchmark(size, density, seed);
+        }
+
+        if layers::split_slices(lines).len() > 1 {
+            bail!("Mode [{}] doesn't support stacked grids", mode);
+        }
+
+        let mut grid = parse_flat(lines)?;
+        let neighbourhood = neighbourhood_for(&grid, params)?;
+
+        if mode == "life" {
+            let rule: Rule = params.get_or("rule", "B3/S23".parse()?)?;
+            let generations = params.get_or("generations", 1000)?;
+
+            let automaton = Automaton::new(rule, neighbourhood);
+
+            return Ok(automaton.run(grid, generations).to_string());
+        }
+
+        let bale_limit = params.get_or("limit", BALE_LIMIT)?;
+        let start = grid.clone();
+        let waves = remove_accessable(&mut grid, bale_limit, &neighbourhood);
+
+        match mode {
+            "waves" => Ok(waves::render_map(&grid, &waves)),
+            "animate" => {
+                let delay = params.get_or("delay", 100)?;
+
+                Ok(waves::animate(&grid, &waves, Duration::from_millis(delay)))
             }
+            "frames" => {
+                let dir: PathBuf = params.require("dir")?;
+                let scale = params.get_or("scale", 4)?;
 
-            if bales_to_remove.is_empty() {
-                break;
+                let count = waves::write_frames(&grid, &waves, &dir, scale)?;
+
+                Ok(format!("wrote {} frames to {}", count, dir.display()))
             }
+            "components" => {
+                let connectivity = Connectivity::from_params(params)?;
 
-            for (bi, bj) in bales_to_remove {
-                grid[bi][bj] = Square::Empty;
+                Ok(components::report(&start, &waves, connectivity))
             }
+            _ => bail!("Mode [{}] isn't supported by day4", mode),
         }
+    }
+}
+
+/// Parses lines into a grid of squares.
+///
+/// `first_line` is the index in the input of `lines[0]`, so that errors
+/// name the input's line numbers.
+///
+/// # Returns
+/// The grid, or an error naming every square that isn't `.` or `@` and
+/// every row whose width differs from the first row's.
+fn parse_grid(lines: &[String], first_line: usize) -> Result<Vec<Vec<Square>>> {
+    let mut errors = vec![];
+    let grid = read_grid(lines, first_line, &mut errors);
+
+    if !errors.is_empty() {
+        bail!("Invalid grid:\n{}", errors.join("\n"));
+    }
+
+    Ok(grid)
+}
+
+/// Reads lines into a grid of squares like [`parse_grid`], but adds the
+/// problems it finds to `errors` instead of failing.
+fn read_grid(lines: &[String], first_line: usize, errors: &mut Vec<String>) -> Vec<Vec<Square>> {
+    let mut grid = vec![];
+
+    for (i, line) in lines.iter().enumerate() {
+        let mut row = vec![];
+
+        for (j, c) in line.chars().enumerate() {
+            match c {
+                '.' => row.push(Square::Empty),
+                '@' => row.push(Square::Bale),
+                _ => errors.push(format!(
+                    "line {}, column {}: `{}` isn't `.` or `@`",
+                    first_line + i + 1,
+                    j + 1,
+                    c
+                )),
+            }
+        }
+
+        let width = line.chars().count();
+        let first_width = lines[0].chars().count();
+
+        if width != first_width {
+            errors.push(format!(
+                "line {}: is {} wide but line {} is {}",
+                first_line + i + 1,
+                width,
+                first_line + 1,
+                first_width
+            ));
+        }
+
+        grid.push(row);
+    }
+
+    grid
+}
+
+/// Parses the input as a single flat grid, ignoring any blank lines before
+/// or after it.
+fn parse_flat(lines: &[String]) -> Result<Vec<Vec<Square>>> {
+    let Some(&(first_line, slice)) = layers::split_slices(lines).first() else {
+        bail!("No grid in input");
+    };
+
+    parse_grid(slice, first_line)
+}
+
+/// Parses the input as a stack of layers if it has more than one slice.
+///
+/// # Returns
+/// The layers, or `None` if the input is a single flat grid.
+fn parse_stack(lines: &[String], params: &Params) -> Result<Option<layers::Layers>> {
+    let slices = layers::split_slices(lines);
+
+    if slices.len() < 2 {
+        return Ok(None);
+    }
+
+    if Neighbourhood::from_params(params)? != Neighbourhood::default() {
+        bail!("Stacked grids always use the 26 surrounding squares as neighbours");
+    }
+
+    Ok(Some(layers::parse_layers(&slices)?))
+}
+
+/// Reads the neighbourhood parameters and checks they suit the grid.
+fn neighbourhood_for(grid: &[Vec<Square>], params: &Params) -> Result<Neighbourhood> {
+    let neighbourhood = Neighbourhood::from_params(params)?;
+    neighbourhood.check(grid.len())?;
+
+    Ok(neighbourhood)
+}
+
+/// Counts the bale neighbours of every square.
+fn neighbour_counts(grid: &[Vec<Square>], neighbourhood: &Neighbourhood) -> Vec<Vec<usize>> {
+    let height = grid.len();
+    let width = grid[0].len();
+
+    let mut counts = vec![vec![0; width]; height];
+
+    for (i, row) in grid.iter().enumerate() {
+        for (j, square) in row.iter().enumerate() {
+            if *square == Square::Bale {
+                for (ni, nj) in neighbourhood.neighbours(i, j, height, width) {
+                    counts[ni][nj] += 1;
+                }
+            }
+        }
+    }
+
+    counts
+}
+
+/// Repeatedly removes accessible bales until none are left, recording the
+/// wave in which each bale was removed.
+///
+/// Rather than rescanning the grid after every wave, each square keeps a
+/// count of its bale neighbours. Removing a bale decrements its neighbours'
+/// counts, and a bale joins the worklist the moment its count drops below
+/// `bale_limit`. Removals only ever lower counts, so the bales removed are
+/// the same as removing every accessible bale in waves, but each square is
+/// visited a bounded number of times.
+///
+/// The worklist is first-in first-out, so a bale that becomes accessible
+/// while wave `w` is being removed is queued behind all of wave `w` and is
+/// removed in wave `w + 1`, exactly as if the grid were rescanned.
+///
+/// # Arguments
+/// * `grid` - The grid of squares; removed bales are set to empty
+/// * `bale_limit` - Maximum number of neighbors for a bale to be accessible
+/// * `neighbourhood` - Which squares count as neighbours
+///
+/// # Returns
+/// For every square, the 1-based wave in which its bale was removed, or
+/// `None` if it was empty or its bale was never removed.
+fn remove_accessable(
+    grid: &mut [Vec<Square>],
+    bale_limit: usize,
+    neighbourhood: &Neighbourhood,
+) -> Vec<Vec<Option<usize>>> {
+    let height = grid.len();
+    let width = grid[0].len();
+
+    let mut counts = neighbour_counts(grid, neighbourhood);
+    let mut waves = vec![vec![None; width]; height];
+    let mut queue = VecDeque::new();
+
+    for i in 0..height {
+        for j in 0..width {
+            if grid[i][j] == Square::Bale && counts[i][j] < bale_limit {
+                // Queued bales are marked empty so they're only queued once
+                grid[i][j] = Square::Empty;
+                queue.push_back((i, j, 1));
+            }
+        }
+    }
 
-        accessable_coords.to_string()
+    while let Some((i, j, wave)) = queue.pop_front() {
+        waves[i][j] = Some(wave);
+
+        for (ni, nj) in neighbourhood.neighbours(i, j, height, width) {
+            counts[ni][nj] -= 1;
+
+            if grid[ni][nj] == Square::Bale && counts[ni][nj] < bale_limit {
+                grid[ni][nj] = Square::Empty;
+                queue.push_back((ni, nj, wave + 1));
+            }
+        }
     }
+
+    waves
+}
+
+/// Counts the bales removed in any wave.
+fn removed_count(waves: &[Vec<Option<usize>>]) -> usize {
+    waves.iter().flatten().filter(|w| w.is_some()).count()
 }
 
 /// Determines if a bale at position (i, j) is accessible.
 ///
 /// A bale is accessible if it exists and has fewer than `bale_limit`
-/// neighboring bales in the 8 cardinal and diagonal directions.
+/// neighboring bales in the neighbourhood (the 8 cardinal and diagonal
+/// directions in the puzzle).
 ///
 /// # Arguments
 /// * `i`, `j` - Grid coordinates to check
 /// * `grid` - The grid of squares
 /// * `bale_limit` - Maximum number of neighbors for a bale to be accessible
+/// * `neighbourhood` - Which squares count as neighbours
 ///
 /// # Returns
 /// `true` if the position contains an accessible bale.
-fn is_accessable(i: isize, j: isize, grid: &Vec<Vec<Square>>, bale_limit: usize) -> bool {
+fn is_accessable(
+    i: isize,
+    j: isize,
+    grid: &[Vec<Square>],
+    bale_limit: usize,
+    neighbourhood: &Neighbourhood,
+) -> bool {
     if i as usize >= grid.len() || j as usize >= grid[0].len() {
         return false;
     }
@@ -123,33 +423,11 @@ fn is_accessable(i: isize, j: isize, grid: &Vec<Vec<Square>>, bale_limit: usize)
         return false;
     }
 
-    let directions = [
-        (-1, -1),
-        (-1, 0),
-        (0, -1),
-        (1, 0),
-        (0, 1),
-        (1, 1),
-        (-1, 1),
-        (1, -1),
-    ];
-
-    let mut bales = 0;
-
-    for (di, dj) in directions {
-        let ni = (i + di) as usize;
-        let nj = (j + dj) as usize;
-
-        if ni as usize >= grid.len() || nj as usize >= grid[0].len() {
-            continue;
-        }
-
-        let neighbor = &grid[ni as usize][nj as usize];
-
-        if *neighbor == Square::Bale {
-            bales += 1;
-        }
-    }
+    let bales = neighbourhood
+        .neighbours(i as usize, j as usize, grid.len(), grid[0].len())
+        .into_iter()
+        .filter(|&(ni, nj)| grid[ni][nj] == Square::Bale)
+        .count();
 
     bales < bale_limit
 }
@@ -157,70 +435,99 @@ fn is_accessable(i: isize, j: isize, grid: &Vec<Vec<Square>>, bale_limit: usize)
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bits::{generate, xorshift};
 
     fn make_grid(lines: &[&str]) -> Vec<Vec<Square>> {
-        lines
-            .iter()
-            .map(|l| {
-                l.chars()
-                    .map(|c| match c {
-                        '.' => Square::Empty,
-                        '@' => Square::Bale,
-                        _ => panic!(),
-                    })
-                    .collect()
-            })
-            .collect()
+        let lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        parse_grid(&lines, 0).unwrap()
+    }
+
+    /// The original part B: rescan the whole grid for accessible bales and
+    /// remove them in waves until nothing changes.
+    fn remove_by_rescanning(
+        grid: &mut [Vec<Square>],
+        bale_limit: usize,
+        neighbourhood: &Neighbourhood,
+    ) -> Vec<Vec<Option<usize>>> {
+        let height = grid.len();
+        let width = grid[0].len();
+
+        let mut waves = vec![vec![None; width]; height];
+        let mut wave = 0;
+
+        loop {
+            wave += 1;
+            let mut bales_to_remove = vec![];
+
+            for i in 0..height {
+                for j in 0..width {
+                    if is_accessable(i as isize, j as isize, grid, bale_limit, neighbourhood) {
+                        bales_to_remove.push((i, j));
+                    }
+                }
+            }
+
+            if bales_to_remove.is_empty() {
+                break;
+            }
+
+            for (bi, bj) in bales_to_remove {
+                grid[bi][bj] = Square::Empty;
+                waves[bi][bj] = Some(wave);
+            }
+        }
+
+        waves
     }
 
     #[test]
     fn test_is_accessable_isolated_bale() {
         let grid = make_grid(&["...", ".@.", "..."]);
         // Isolated bale has 0 neighbors, should be accessible with any bale_limit > 0
-        assert!(is_accessable(1, 1, &grid, 4));
-        assert!(is_accessable(1, 1, &grid, 1));
+        assert!(is_accessable(1, 1, &grid, 4, &Neighbourhood::default()));
+        assert!(is_accessable(1, 1, &grid, 1, &Neighbourhood::default()));
     }
 
     #[test]
     fn test_is_accessable_surrounded_bale() {
         let grid = make_grid(&["@@@", "@@@", "@@@"]);
         // Center bale has 8 neighbors
-        assert!(!is_accessable(1, 1, &grid, 4)); // 8 >= 4, not accessible
-        assert!(!is_accessable(1, 1, &grid, 8)); // 8 >= 8, not accessible
-        assert!(is_accessable(1, 1, &grid, 9)); // 8 < 9, accessible
+        assert!(!is_accessable(1, 1, &grid, 4, &Neighbourhood::default())); // 8 >= 4, not accessible
+        assert!(!is_accessable(1, 1, &grid, 8, &Neighbourhood::default())); // 8 >= 8, not accessible
+        assert!(is_accessable(1, 1, &grid, 9, &Neighbourhood::default())); // 8 < 9, accessible
     }
 
     #[test]
     fn test_is_accessable_corner_bale() {
         let grid = make_grid(&["@.", ".."]);
         // Corner bale at (0,0) has only 3 possible neighbors, all empty
-        assert!(is_accessable(0, 0, &grid, 4));
-        assert!(is_accessable(0, 0, &grid, 1));
+        assert!(is_accessable(0, 0, &grid, 4, &Neighbourhood::default()));
+        assert!(is_accessable(0, 0, &grid, 1, &Neighbourhood::default()));
     }
 
     #[test]
     fn test_is_accessable_empty_square() {
         let grid = make_grid(&["...", ".@.", "..."]);
         // Empty square is never accessible (must be a bale)
-        assert!(!is_accessable(0, 0, &grid, 4));
+        assert!(!is_accessable(0, 0, &grid, 4, &Neighbourhood::default()));
     }
 
     #[test]
     fn test_is_accessable_out_of_bounds() {
         let grid = make_grid(&["@"]);
-        assert!(!is_accessable(-1, 0, &grid, 4));
-        assert!(!is_accessable(0, -1, &grid, 4));
-        assert!(!is_accessable(1, 0, &grid, 4));
-        assert!(!is_accessable(0, 1, &grid, 4));
+        assert!(!is_accessable(-1, 0, &grid, 4, &Neighbourhood::default()));
+        assert!(!is_accessable(0, -1, &grid, 4, &Neighbourhood::default()));
+        assert!(!is_accessable(1, 0, &grid, 4, &Neighbourhood::default()));
+        assert!(!is_accessable(0, 1, &grid, 4, &Neighbourhood::default()));
     }
 
     #[test]
     fn test_is_accessable_partial_neighbors() {
         let grid = make_grid(&["@@.", "@..", "..."]);
         // Bale at (0,0) has 2 bale neighbors: (0,1) and (1,0)
-        assert!(is_accessable(0, 0, &grid, 4)); // 2 < 4
-        assert!(is_accessable(0, 0, &grid, 3)); // 2 < 3
-        assert!(!is_accessable(0, 0, &grid, 2)); // 2 >= 2
+        assert!(is_accessable(0, 0, &grid, 4, &Neighbourhood::default())); // 2 < 4
+        assert!(is_accessable(0, 0, &grid, 3, &Neighbourhood::default())); // 2 < 3
+        assert!(!is_accessable(0, 0, &grid, 2, &Neighbourhood::default())); // 2 >= 2
     }
 
     #[test]
@@ -265,4 +572,232 @@ mod tests {
         let input = vec!["...".to_string(), "...".to_string()];
         assert_eq!(Day4::part_b(&input), "0");
     }
+
+    #[test]
+    fn test_parts_ragged_rows() {
+        let input: Vec<String> = ["", "@@@", "@@", "@@@", "@@@"]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        let expected = "Invalid grid:\nline 3: is 2 wide but line 2 is 3";
+
+        for args in [
+            vec![],
+            vec!["neighbourhood=von-neumann"],
+            vec!["neighbourhood=hex"],
+            vec!["radius=2"],
+            vec!["wrap"],
+        ] {
+            let params = Params::from_strs(&args);
+            let err = Day4::part_a_with(&input, &params).unwrap_err();
+            assert_eq!(err.to_string(), expected);
+            let err = Day4::part_b_with(&input, &params).unwrap_err();
+            assert_eq!(err.to_string(), expected);
+        }
+
+        for mode in ["waves", "components", "life"] {
+            let err = Day4::mode(&input, mode, &Params::default()).unwrap_err();
+            assert_eq!(err.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_parts_bad_squares() {
+        let input: Vec<String> = ["@.@", "@x@", "@@#"]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        let err = Day4::part_a_with(&input, &Params::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid grid:\nline 2, column 2: `x` isn't `.` or `@`\n\
+             line 3, column 3: `#` isn't `.` or `@`"
+        );
+    }
+
+    #[test]
+    fn test_parts_surrounding_blank_lines() {
+        let input: Vec<String> = ["", "@@", "@@", "", ""]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        assert_eq!(Day4::part_a(&input), "4");
+        assert_eq!(Day4::part_b(&input), "4");
+        assert_eq!(
+            Day4::mode(&input, "waves", &Params::default()).unwrap(),
+            Day4::mode(&input[1..3], "waves", &Params::default()).unwrap()
+        );
+
+        assert!(Day4::part_a_with(&["".to_string()], &Params::default()).is_err());
+    }
+
+    #[test]
+    fn test_neighbour_counts() {
+        let grid = make_grid(&["@@.", "@..", "..@"]);
+        assert_eq!(
+            neighbour_counts(&grid, &Neighbourhood::default()),
+            vec![vec![2, 2, 1], vec![2, 4, 2], vec![1, 2, 0]]
+        );
+    }
+
+    #[test]
+    fn test_remove_accessable_matches_rescanning() {
+        // Pseudo-random grids of varying size, density and limit
+        let mut state: u64 = 0x9e3779b97f4a7c15;
+
+        for _ in 0..100 {
+            let height = (xorshift(&mut state) % 20 + 1) as usize;
+            let width = (xorshift(&mut state) % 20 + 1) as usize;
+            let density = xorshift(&mut state) % 100;
+            let bale_limit = (xorshift(&mut state) % 9) as usize;
+
+            let mut worklist = generate(height, width, density, xorshift(&mut state));
+            let mut rescanned = worklist.clone();
+
+            let waves = remove_accessable(&mut worklist, bale_limit, &Neighbourhood::default());
+            let expected =
+                remove_by_rescanning(&mut rescanned, bale_limit, &Neighbourhood::default());
+
+            assert_eq!(waves, expected);
+            assert_eq!(worklist, rescanned);
+        }
+    }
+
+    #[test]
+    fn test_parts_von_neumann() {
+        let input = vec!["@@@".to_string(), "@@@".to_string(), "@@@".to_string()];
+        let params = Params::from_strs(&["neighbourhood=von-neumann"]);
+        // Corners have 2 neighbours and edges 3, the centre 4
+        assert_eq!(Day4::part_a_with(&input, &params).unwrap(), "8");
+        assert_eq!(Day4::part_b_with(&input, &params).unwrap(), "9");
+    }
+
+    #[test]
+    fn test_parts_moore_radius() {
+        let input = vec!["@@@@@".to_string(); 5];
+        let params = Params::from_strs(&["radius=2"]);
+        // Even the corners see 8 bales within radius 2
+        assert_eq!(Day4::part_a_with(&input, &params).unwrap(), "0");
+
+        // Each bale sees only one other, two squares away
+        let sparse = vec![
+            "@...@".to_string(),
+            ".....".to_string(),
+            "..@..".to_string(),
+        ];
+        assert_eq!(Day4::part_a_with(&sparse, &params).unwrap(), "3");
+    }
+
+    #[test]
+    fn test_parts_hexagonal() {
+        let input = vec!["@@@".to_string(), "@@@".to_string(), "@@@".to_string()];
+        let params = Params::from_strs(&["neighbourhood=hex"]);
+        // Even rows see 2, 4 and 3 bales; the odd middle row sees 5, 6 and 3
+        assert_eq!(Day4::part_a_with(&input, &params).unwrap(), "5");
+        assert_eq!(Day4::part_b_with(&input, &params).unwrap(), "9");
+    }
+
+    #[test]
+    fn test_parts_wrap() {
+        // On a torus a solid block has no edges, so nothing is accessible
+        let input = vec!["@@@@".to_string(); 4];
+        assert_eq!(Day4::part_a(&input), "4");
+        assert_eq!(
+            Day4::part_a_with(&input, &Params::from_strs(&["wrap"])).unwrap(),
+            "0"
+        );
+        assert_eq!(
+            Day4::part_b_with(&input, &Params::from_strs(&["wrap"])).unwrap(),
+            "0"
+        );
+    }
+
+    #[test]
+    fn test_hexagonal_wrap_odd_rows() {
+        let input = vec!["@@@".to_string(); 3];
+        assert!(
+            Day4::part_a_with(&input, &Params::from_strs(&["neighbourhood=hex", "wrap"])).is_err()
+        );
+    }
+
+    #[test]
+    fn test_parts_limit() {
+        let input = vec!["@@@".to_string(), "@@@".to_string(), "@@@".to_string()];
+        // Edges have 5 neighbours and the centre 8
+        assert_eq!(
+            Day4::part_a_with(&input, &Params::from_strs(&["limit=6"])).unwrap(),
+            "8"
+        );
+        assert_eq!(
+            Day4::part_a_with(&input, &Params::from_strs(&["limit=3"])).unwrap(),
+            "0"
+        );
+        assert_eq!(
+            Day4::part_b_with(&input, &Params::from_strs(&["limit=3"])).unwrap(),
+            "0"
+        );
+    }
+
+    #[test]
+    fn test_parts_stacked() {
+        let input: Vec<String> = ["@@@", "@@@", "@@@", "", "...", ".@.", "...", ""]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        // The top bale leans on all 9 below it, so only the bottom corners
+        // (4 neighbours) and edges (6) are accessible with a limit of 7
+        assert_eq!(Day4::part_a(&input), "0");
+        assert_eq!(
+            Day4::part_a_with(&input, &Params::from_strs(&["limit=7"])).unwrap(),
+            "8"
+        );
+        assert_eq!(
+            Day4::part_b_with(&input, &Params::from_strs(&["limit=7"])).unwrap(),
+            "10"
+        );
+
+        assert!(Day4::part_a_with(&input, &Params::from_strs(&["neighbourhood=hex"])).is_err());
+        assert!(Day4::mode(&input, "waves", &Params::default()).is_err());
+    }
+
+    #[test]
+    fn test_neighbourhoods_match_rescanning() {
+        let lines: Vec<String> = (0..12u64)
+            .map(|i| {
+                (0..15u64)
+                    .map(|j| {
+                        if (i * 7 + j * 13 + i * j) % 5 < 3 {
+                            '@'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        for args in [
+            vec!["neighbourhood=von-neumann"],
+            vec!["neighbourhood=von-neumann", "radius=2", "wrap"],
+            vec!["radius=2"],
+            vec!["radius=3", "wrap"],
+            vec!["neighbourhood=hex"],
+            vec!["neighbourhood=hex", "wrap"],
+        ] {
+            let neighbourhood = Neighbourhood::from_params(&Params::from_strs(&args)).unwrap();
+
+            for bale_limit in [2, 4, 8] {
+                let mut worklist = parse_grid(&lines, 0).unwrap();
+                let mut rescanned = worklist.clone();
+
+                assert_eq!(
+                    remove_accessable(&mut worklist, bale_limit, &neighbourhood),
+                    remove_by_rescanning(&mut rescanned, bale_limit, &neighbourhood),
+                    "{:?}, limit {}",
+                    args,
+                    bale_limit
+                );
+            }
+        }
+    }
 }
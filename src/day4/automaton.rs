@@ -0,0 +1,420 @@
+//! A life-like cellular automaton running on day4's grid of squares.
+//!
+//! Part B's removal is one such automaton: a bale survives while it has at
+//! least 4 neighbours and no bale is ever born, which is the rule
+//! `B/S45678`. This module runs any birth/survival rule over any
+//! [`Neighbourhood`], stopping after a number of generations or as soon as
+//! the grid repeats an earlier state.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use anyhow::{Error, Result, bail};
+
+use super::neighbourhood::Neighbourhood;
+use super::{Square, neighbour_counts};
+
+/// A birth/survival rule such as `B3/S23` (Conway's Game of Life).
+///
+/// An empty square becomes a bale if its number of bale neighbours is one
+/// of the birth counts; a bale stays if its count is one of the survival
+/// counts. Counts are single digits, so at most 9 neighbours can be named.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    /// Bit `n` is set if `n` neighbours give birth to a bale
+    birth: u16,
+    /// Bit `n` is set if a bale with `n` neighbours survives
+    survival: u16,
+}
+
+impl Rule {
+    /// Finds whether a square holds a bale in the next generation.
+    pub fn next(&self, square: Square, neighbours: usize) -> Square {
+        let counts = match square {
+            Square::Empty => self.birth,
+            Square::Bale => self.survival,
+        };
+
+        if neighbours < 16 && counts & (1 << neighbours) != 0 {
+            Square::Bale
+        } else {
+            Square::Empty
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = Error;
+
+    /// Parses `B<digits>/S<digits>`, in either order and either case.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut birth = None;
+        let mut survival = None;
+
+        for part in s.split('/') {
+            let (kind, digits) = part.split_at_checked(1).unwrap_or(("", ""));
+
+            let mut counts = 0;
+            for c in digits.chars() {
+                let Some(n) = c.to_digit(10) else {
+                    bail!("Rule [{}] has a non-digit count `{}`", s, c);
+                };
+                counts |= 1 << n;
+            }
+
+            let slot = match kind {
+                "B" | "b" => &mut birth,
+                "S" | "s" => &mut survival,
+                _ => bail!("Rule [{}] must look like B3/S23", s),
+            };
+
+            if slot.replace(counts).is_some() {
+                bail!("Rule [{}] repeats `{}`", s, kind);
+            }
+        }
+
+        match (birth, survival) {
+            (Some(birth), Some(survival)) => Ok(Rule { birth, survival }),
+            _ => bail!("Rule [{}] must look like B3/S23", s),
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = |counts: u16| -> String {
+            (0..10)
+                .filter(|n| counts & (1 << n) != 0)
+                .map(|n| n.to_string())
+                .collect()
+        };
+
+        write!(f, "B{}/S{}", digits(self.birth), digits(self.survival))
+    }
+}
+
+/// Why a run of the automaton stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum End {
+    /// The generation limit was reached without the grid repeating
+    Limit,
+    /// The grid stopped changing
+    FixedPoint,
+    /// The grid returned to the state it had at generation `start`,
+    /// repeating every `length` generations
+    Cycle { start: usize, length: usize },
+}
+
+/// The result of running the automaton.
+#[derive(Debug)]
+pub struct Outcome {
+    /// The grid after the last generation run
+    pub grid: Vec<Vec<Square>>,
+    /// How many generations were run
+    pub generations: usize,
+    /// Why the run stopped
+    pub end: End,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in &self.grid {
+            let line: String = row
+                .iter()
+                .map(|s| match s {
+                    Square::Empty => '.',
+                    Square::Bale => '@',
+                })
+                .collect();
+            writeln!(f, "{}", line)?;
+        }
+
+        let population = self
+            .grid
+            .iter()
+            .flatten()
+            .filter(|s| **s == Square::Bale)
+            .count();
+        writeln!(f, "population: {}", population)?;
+
+        match self.end {
+            End::Limit => write!(f, "stopped after {} generations", self.generations),
+            End::FixedPoint => write!(
+                f,
+                "fixed point reached after {} generations",
+                self.generations - 1
+            ),
+            End::Cycle { start, length } => write!(
+                f,
+                "cycle of length {} entered at generation {}",
+                length, start
+            ),
+        }
+    }
+}
+
+/// A rule together with the neighbourhood it counts bales in.
+pub struct Automaton {
+    rule: Rule,
+    neighbourhood: Neighbourhood,
+}
+
+impl Automaton {
+    /// Creates an automaton applying `rule` over `neighbourhood`.
+    pub fn new(rule: Rule, neighbourhood: Neighbourhood) -> Self {
+        Automaton {
+            rule,
+            neighbourhood,
+        }
+    }
+
+    /// Computes the next generation of the grid.
+    pub fn step(&self, grid: &[Vec<Square>]) -> Vec<Vec<Square>> {
+        let counts = neighbour_counts(grid, &self.neighbourhood);
+
+        grid.iter()
+            .zip(counts)
+            .map(|(row, row_counts)| {
+                row.iter()
+                    .zip(row_counts)
+                    .map(|(&square, count)| self.rule.next(square, count))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Steps the grid until `max_generations` have run or a state repeats.
+    ///
+    /// Every state seen is remembered by a 64-bit fingerprint with its
+    /// generation, so a repeat is spotted the first time it happens without
+    /// keeping every grid. A fingerprint match is confirmed by comparing
+    /// the grids: the previous generation is kept, and any earlier one is
+    /// rebuilt by stepping again from the start. A state that repeats
+    /// immediately is a fixed point; the run then counts the generation
+    /// that confirmed it.
+    ///
+    /// # Arguments
+    /// * `grid` - The starting grid (generation 0)
+    /// * `max_generations` - The most generations to run
+    pub fn run(&self, grid: Vec<Vec<Square>>, max_generations: usize) -> Outcome {
+        let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+        let first = grid.clone();
+        let mut grid = grid;
+
+        seen.entry(fingerprint(&grid)).or_default().push(0);
+
+        for generation in 1..=max_generations {
+            let previous = grid;
+            grid = self.step(&previous);
+
+            let earlier = seen.entry(fingerprint(&grid)).or_default();
+
+            let repeat = earlier.iter().copied().find(|&start| {
+                if start == generation - 1 {
+                    previous == grid
+                } else {
+                    self.replay(&first, start) == grid
+                }
+            });
+
+            if let Some(start) = repeat {
+                let length = generation - start;
+
+                let end = if length == 1 {
+                    End::FixedPoint
+                } else {
+                    End::Cycle { start, length }
+                };
+
+                return Outcome {
+                    grid,
+                    generations: generation,
+                    end,
+                };
+            }
+
+            earlier.push(generation);
+        }
+
+        Outcome {
+            grid,
+            generations: max_generations,
+            end: End::Limit,
+        }
+    }
+
+    /// Rebuilds the grid at `generation` by stepping from the starting grid.
+    fn replay(&self, first: &[Vec<Square>], generation: usize) -> Vec<Vec<Square>> {
+        (0..generation).fold(first.to_vec(), |grid, _| self.step(&grid))
+    }
+}
+
+/// Hashes a grid down to 64 bits.
+fn fingerprint(grid: &[Vec<Square>]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    grid.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day4::bits::generate;
+    use crate::day4::neighbourhood::Shape;
+    use crate::day4::{BALE_LIMIT, parse_grid, remove_accessable};
+
+    fn grid(lines: &[&str]) -> Vec<Vec<Square>> {
+        let lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        parse_grid(&lines, 0).unwrap()
+    }
+
+    fn life() -> Automaton {
+        Automaton::new("B3/S23".parse().unwrap(), Neighbourhood::default())
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        let rule: Rule = "B3/S23".parse().unwrap();
+        assert_eq!(rule.to_string(), "B3/S23");
+        assert_eq!("s23/b3".parse::<Rule>().unwrap(), rule);
+        assert_eq!("B/S45678".parse::<Rule>().unwrap().to_string(), "B/S45678");
+
+        assert!("B3".parse::<Rule>().is_err());
+        assert!("B3/S2x".parse::<Rule>().is_err());
+        assert!("B3/B2".parse::<Rule>().is_err());
+        assert!("23/3".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn test_block_is_fixed_point() {
+        let outcome = life().run(grid(&["....", ".@@.", ".@@.", "...."]), 10);
+        assert_eq!(outcome.end, End::FixedPoint);
+        assert_eq!(outcome.generations, 1);
+    }
+
+    #[test]
+    fn test_blinker_cycles() {
+        let start = grid(&[".....", "..@..", "..@..", "..@..", "....."]);
+        let outcome = life().run(start, 10);
+        assert_eq!(
+            outcome.end,
+            End::Cycle {
+                start: 0,
+                length: 2
+            }
+        );
+        assert_eq!(
+            outcome.to_string(),
+            ".....\n..@..\n..@..\n..@..\n.....\npopulation: 3\n\
+             cycle of length 2 entered at generation 0"
+        );
+    }
+
+    #[test]
+    fn test_glider_on_torus() {
+        // A glider returns to its shape and position every 4 * size
+        // generations on a wrapped grid
+        let start = grid(&[
+            ".@......", "..@.....", "@@@.....", "........", "........", "........", "........",
+            "........",
+        ]);
+        let torus = Automaton::new(
+            "B3/S23".parse().unwrap(),
+            Neighbourhood::new(Shape::Moore { radius: 1 }, true),
+        );
+
+        assert_eq!(torus.run(start.clone(), 31).end, End::Limit);
+        assert_eq!(
+            torus.run(start, 100).end,
+            End::Cycle {
+                start: 0,
+                length: 32
+            }
+        );
+    }
+
+    /// Runs the automaton remembering every full grid, as the fingerprinted
+    /// [`Automaton::run`] should behave.
+    fn run_keeping_grids(
+        automaton: &Automaton,
+        mut grid: Vec<Vec<Square>>,
+        max_generations: usize,
+    ) -> (usize, End) {
+        let mut seen = HashMap::new();
+        seen.insert(grid.clone(), 0);
+
+        for generation in 1..=max_generations {
+            grid = automaton.step(&grid);
+
+            if let Some(&start) = seen.get(&grid) {
+                let length = generation - start;
+                let end = if length == 1 {
+                    End::FixedPoint
+                } else {
+                    End::Cycle { start, length }
+                };
+                return (generation, end);
+            }
+
+            seen.insert(grid.clone(), generation);
+        }
+
+        (max_generations, End::Limit)
+    }
+
+    #[test]
+    fn test_run_matches_keeping_grids() {
+        let torus = Automaton::new(
+            "B3/S23".parse().unwrap(),
+            Neighbourhood::new(Shape::Moore { radius: 1 }, true),
+        );
+        let mut found_late_cycle = false;
+
+        for seed in 1..=40 {
+            let start = generate(6, 6, 40, seed);
+            let outcome = torus.run(start.clone(), 300);
+
+            assert_eq!(
+                (outcome.generations, outcome.end),
+                run_keeping_grids(&torus, start, 300),
+                "seed {}",
+                seed
+            );
+            found_late_cycle |= matches!(outcome.end, End::Cycle { start, .. } if start > 0);
+        }
+
+        // Make sure the replayed comparison was exercised
+        assert!(found_late_cycle);
+    }
+
+    #[test]
+    fn test_removal_rule_matches_part_b() {
+        let lines = vec![
+            "..@@.@@@@.",
+            "@@@.@.@.@@",
+            "@@@@@.@.@@",
+            "@.@@@@..@.",
+            "@@.@@@@.@@",
+            ".@@@@@@@.@",
+            ".@.@.@.@@@",
+            "@.@@@.@@@@",
+            ".@@@@@@@@.",
+            "@.@.@@@.@.",
+        ];
+
+        let mut removed = grid(&lines);
+        let waves = remove_accessable(&mut removed, BALE_LIMIT, &Neighbourhood::default());
+        let last_wave = waves.iter().flatten().flatten().max().copied().unwrap();
+
+        let removal = Automaton::new("B/S45678".parse().unwrap(), Neighbourhood::default());
+        let outcome = removal.run(grid(&lines), 100);
+
+        assert_eq!(outcome.grid, removed);
+        assert_eq!(outcome.end, End::FixedPoint);
+        assert_eq!(outcome.generations, last_wave + 1);
+    }
+}
@@ -0,0 +1,548 @@
+//! A bit-packed bale grid for the puzzle's own neighbourhood.
+//!
+//! Each row is stored as a run of `u64` words with one bit per square, and
+//! all rows live in one contiguous buffer. Neighbour counts are computed 64
+//! squares at a time: the rows above, at and below a word are shifted one
+//! bit left and right to line up the 8 neighbours, and the resulting bit
+//! planes are summed with a bit-sliced adder so that each bit position holds
+//! its own 4-bit count.
+
+use std::time::Instant;
+
+use anyhow::{Result, bail};
+
+use super::neighbourhood::Neighbourhood;
+use super::{BALE_LIMIT, Square, is_accessable, remove_accessable, removed_count};
+
+/// Bits in each word of a row.
+const WORD_BITS: usize = 64;
+
+/// Bit planes of a 4-bit count per bit position, least significant first.
+type Count = [u64; 4];
+
+/// A grid of bales packed one bit per square.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitGrid {
+    height: usize,
+    width: usize,
+    /// Words in each row
+    stride: usize,
+    /// Row-major words; bit `j % 64` of word `j / 64` is column `j`, and
+    /// bits past the last column are always clear
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    /// Packs a grid of squares.
+    ///
+    /// # Returns
+    /// The packed grid, or an error naming every row whose width differs
+    /// from the first row's.
+    pub fn new(grid: &[Vec<Square>]) -> Result<Self> {
+        let height = grid.len();
+        let width = grid.first().map_or(0, |row| row.len());
+        let stride = width.div_ceil(WORD_BITS);
+
+        let errors: Vec<String> = grid
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| row.len() != width)
+            .map(|(i, row)| {
+                format!(
+                    "row {}: is {} wide but row 1 is {}",
+                    i + 1,
+                    row.len(),
+                    width
+                )
+            })
+            .collect();
+
+        if !errors.is_empty() {
+            bail!("Invalid grid:\n{}", errors.join("\n"));
+        }
+
+        let mut words = vec![0; height * stride];
+
+        for (i, row) in grid.iter().enumerate() {
+            for (j, square) in row.iter().enumerate() {
+                if *square == Square::Bale {
+                    words[i * stride + j / WORD_BITS] |= 1 << (j % WORD_BITS);
+                }
+            }
+        }
+
+        Ok(BitGrid {
+            height,
+            width,
+            stride,
+            words,
+        })
+    }
+
+    /// Unpacks the grid back into squares.
+    #[cfg(test)]
+    pub fn to_squares(&self) -> Vec<Vec<Square>> {
+        (0..self.height)
+            .map(|i| {
+                (0..self.width)
+                    .map(|j| {
+                        if self.words[i * self.stride + j / WORD_BITS] >> (j % WORD_BITS) & 1 == 1 {
+                            Square::Bale
+                        } else {
+                            Square::Empty
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Counts the bales in the grid.
+    #[cfg(test)]
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Finds the bales with fewer than `bale_limit` of their 8 neighbours
+    /// holding bales.
+    ///
+    /// # Returns
+    /// A mask in the same layout as the grid's words.
+    pub fn accessible(&self, bale_limit: usize) -> Vec<u64> {
+        let empty = vec![0; self.stride];
+
+        (0..self.words.len())
+            .map(|w| self.accessible_word(w, bale_limit, &empty))
+            .collect()
+    }
+
+    /// Finds the accessible bales in word `w` alone.
+    ///
+    /// # Arguments
+    /// * `w` - Index of the word in the grid's buffer
+    /// * `bale_limit` - Bales with fewer neighbours than this are accessible
+    /// * `empty` - A row of `stride` clear words, standing in for the rows
+    ///   beyond the grid's edges
+    fn accessible_word(&self, w: usize, bale_limit: usize, empty: &[u64]) -> u64 {
+        let (i, k) = (w / self.stride, w % self.stride);
+
+        let above = if i > 0 { self.row(i - 1) } else { empty };
+        let row = self.row(i);
+        let below = if i + 1 < self.height {
+            self.row(i + 1)
+        } else {
+            empty
+        };
+
+        let mut count: Count = [0; 4];
+
+        for r in [above, row, below] {
+            add(&mut count, shifted_left(r, k));
+            add(&mut count, shifted_right(r, k));
+        }
+        add(&mut count, above[k]);
+        add(&mut count, below[k]);
+
+        row[k] & less_than(&count, bale_limit)
+    }
+
+    /// Counts the accessible bales, as in part A.
+    pub fn accessible_count(&self, bale_limit: usize) -> usize {
+        self.accessible(bale_limit)
+            .iter()
+            .map(|w| w.count_ones() as usize)
+            .sum()
+    }
+
+    /// Removes accessible bales a whole wave at a time until none are left,
+    /// as in part B.
+    ///
+    /// Only the first wave scans every word. A bale can only become
+    /// accessible when one of its neighbours is removed, so each later wave
+    /// re-examines just the words touching the previous wave's removals,
+    /// much like the square-by-square worklist.
+    ///
+    /// # Returns
+    /// The number of bales removed.
+    pub fn remove_accessible(&mut self, bale_limit: usize) -> usize {
+        let empty = vec![0; self.stride];
+        let mut queued = vec![false; self.words.len()];
+        let mut candidates: Vec<usize> = (0..self.words.len()).collect();
+        let mut removed = 0;
+
+        loop {
+            // The whole wave is found before any of it is removed
+            let wave: Vec<(usize, u64)> = candidates
+                .iter()
+                .map(|&w| (w, self.accessible_word(w, bale_limit, &empty)))
+                .filter(|&(_, mask)| mask != 0)
+                .collect();
+
+            if wave.is_empty() {
+                return removed;
+            }
+
+            for &(w, mask) in &wave {
+                self.words[w] &= !mask;
+                removed += mask.count_ones() as usize;
+            }
+
+            candidates.clear();
+
+            for &(w, mask) in &wave {
+                for n in self.touched_words(w, mask) {
+                    if !queued[n] && self.words[n] != 0 {
+                        queued[n] = true;
+                        candidates.push(n);
+                    }
+                }
+            }
+
+            for &n in &candidates {
+                queued[n] = false;
+            }
+        }
+    }
+
+    /// Finds the words holding neighbours of the squares set in `mask`,
+    /// which lies in word `w`, including `w` itself.
+    fn touched_words(&self, w: usize, mask: u64) -> impl Iterator<Item = usize> + use<> {
+        let (i, k) = (w / self.stride, w % self.stride);
+
+        // The neighbouring words only matter if an edge bit was removed
+        let first = if mask & 1 == 1 {
+            k.saturating_sub(1)
+        } else {
+            k
+        };
+        let last = if mask >> (WORD_BITS - 1) == 1 {
+            (k + 1).min(self.stride - 1)
+        } else {
+            k
+        };
+
+        let rows = i.saturating_sub(1)..(i + 2).min(self.height);
+        let stride = self.stride;
+
+        rows.flat_map(move |r| (first..=last).map(move |c| r * stride + c))
+    }
+
+    /// The words of row `i`.
+    fn row(&self, i: usize) -> &[u64] {
+        &self.words[i * self.stride..(i + 1) * self.stride]
+    }
+}
+
+/// Lines up each square with its left neighbour: bit `j` of the result is
+/// column `j - 1` of the row.
+fn shifted_left(row: &[u64], k: usize) -> u64 {
+    let carry = if k > 0 {
+        row[k - 1] >> (WORD_BITS - 1)
+    } else {
+        0
+    };
+    (row[k] << 1) | carry
+}
+
+/// Lines up each square with its right neighbour: bit `j` of the result is
+/// column `j + 1` of the row.
+fn shifted_right(row: &[u64], k: usize) -> u64 {
+    let carry = if k + 1 < row.len() {
+        row[k + 1] << (WORD_BITS - 1)
+    } else {
+        0
+    };
+    (row[k] >> 1) | carry
+}
+
+/// Adds a one-bit plane to a bit-sliced count, rippling the carry upwards.
+fn add(count: &mut Count, plane: u64) {
+    let mut carry = plane;
+
+    for bit in count.iter_mut() {
+        let next = *bit & carry;
+        *bit ^= carry;
+        carry = next;
+    }
+}
+
+/// Finds the bit positions whose count is below `limit`.
+///
+/// Compares from the most significant plane down, tracking which positions
+/// are still equal to `limit` so far.
+fn less_than(count: &Count, limit: usize) -> u64 {
+    if limit >= 1 << count.len() {
+        return !0;
+    }
+
+    let mut less = 0;
+    let mut equal = !0;
+
+    for (bit, plane) in count.iter().enumerate().rev() {
+        if limit >> bit & 1 == 1 {
+            less |= equal & !plane;
+            equal &= plane;
+        } else {
+            equal &= !plane;
+        }
+    }
+
+    less
+}
+
+/// Times both parts with the bit-packed grid against the square-by-square
+/// grid on generated grids, checking that both give the same answers.
+///
+/// Two grids are timed: a random one, which clears in a handful of waves,
+/// and one of two-row strips, which are eaten from both ends one column
+/// per wave and so take thousands of waves.
+///
+/// # Arguments
+/// * `size` - Width and height of the generated grids
+/// * `density` - Percentage of squares holding a bale in the random grid
+/// * `seed` - Seed for the pseudo-random generator
+///
+/// # Returns
+/// A report of the timings and speed-ups.
+pub fn benchmark(size: usize, density: u64, seed: u64) -> Result<String> {
+    if size == 0 || seed == 0 {
+        bail!("Benchmark size and seed must be at least 1");
+    }
+
+    let mut out = vec![format!(
+        "grid: {size}x{size}, {density}% bales, seed {seed}"
+    )];
+    out.extend(compare(generate(size, size, density, seed))?);
+
+    out.push(format!("grid: {size}x{size}, two-row strips"));
+    out.extend(compare(strips(size))?);
+
+    Ok(out.join("\n"))
+}
+
+/// Times both parts on one grid, returning a report line per part.
+fn compare(grid: Vec<Vec<Square>>) -> Result<Vec<String>> {
+    let (height, width) = (grid.len(), grid[0].len());
+
+    let start = Instant::now();
+    let mut expected_a = 0;
+    for i in 0..height {
+        for j in 0..width {
+            if is_accessable(
+                i as isize,
+                j as isize,
+                &grid,
+                BALE_LIMIT,
+                &Neighbourhood::default(),
+            ) {
+                expected_a += 1;
+            }
+        }
+    }
+    let squares_a = start.elapsed();
+
+    let start = Instant::now();
+    let bits = BitGrid::new(&grid)?;
+    let actual_a = bits.accessible_count(BALE_LIMIT);
+    let bits_a = start.elapsed();
+
+    let start = Instant::now();
+    let mut squares = grid;
+    let expected_b = removed_count(&remove_accessable(
+        &mut squares,
+        BALE_LIMIT,
+        &Neighbourhood::default(),
+    ));
+    let squares_b = start.elapsed();
+    drop(squares);
+
+    let start = Instant::now();
+    let mut bits = bits;
+    let actual_b = bits.remove_accessible(BALE_LIMIT);
+    let bits_b = start.elapsed();
+
+    if (actual_a, actual_b) != (expected_a, expected_b) {
+        bail!(
+            "Bit-packed answers {} and {} differ from {} and {}",
+            actual_a,
+            actual_b,
+            expected_a,
+            expected_b
+        );
+    }
+
+    Ok([
+        ("a", actual_a, squares_a, bits_a),
+        ("b", actual_b, squares_b, bits_b),
+    ]
+    .iter()
+    .map(|(part, answer, squares, bits)| {
+        format!(
+            "part {}: {} (squares {:.3?}, bits {:.3?}, {:.1}x faster)",
+            part,
+            answer,
+            squares,
+            bits,
+            squares.as_secs_f64() / bits.as_secs_f64()
+        )
+    })
+    .collect())
+}
+
+/// Generates a square grid of horizontal strips two bales high, each
+/// separated by an empty row.
+fn strips(size: usize) -> Vec<Vec<Square>> {
+    (0..size)
+        .map(|i| {
+            let square = if i % 3 == 2 {
+                Square::Empty
+            } else {
+                Square::Bale
+            };
+
+            vec![square; size]
+        })
+        .collect()
+}
+
+/// Generates a `height` by `width` grid where about `density` percent of
+/// the squares hold bales, using [`xorshift`] seeded with `seed`.
+pub fn generate(height: usize, width: usize, density: u64, seed: u64) -> Vec<Vec<Square>> {
+    let mut state = seed;
+
+    (0..height)
+        .map(|_| {
+            (0..width)
+                .map(|_| {
+                    if xorshift(&mut state) % 100 < density {
+                        Square::Bale
+                    } else {
+                        Square::Empty
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Advances a xorshift pseudo-random generator, returning its new state.
+///
+/// The state must start non-zero.
+pub fn xorshift(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+
+    *state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let grid = generate(5, 130, 67, 1);
+        let bits = BitGrid::new(&grid).unwrap();
+        assert_eq!(bits.to_squares(), grid);
+        assert_eq!(
+            bits.count(),
+            grid.iter()
+                .flatten()
+                .filter(|s| **s == Square::Bale)
+                .count()
+        );
+    }
+
+    #[test]
+    fn test_new_rejects_ragged_rows() {
+        let grid = vec![
+            vec![Square::Bale; 3],
+            vec![Square::Bale; 2],
+            vec![Square::Empty; 3],
+            vec![],
+        ];
+        let err = BitGrid::new(&grid).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "Invalid grid:\nrow 2: is 2 wide but row 1 is 3\nrow 4: is 0 wide but row 1 is 3"
+        );
+    }
+
+    #[test]
+    fn test_benchmark() {
+        let report = benchmark(100, 70, 3).unwrap();
+        assert!(report.starts_with("grid: 100x100, 70% bales, seed 3\npart a: "));
+        assert!(report.contains("\ngrid: 100x100, two-row strips\npart a: "));
+        assert!(benchmark(0, 70, 3).is_err());
+    }
+
+    #[test]
+    fn test_add_and_less_than() {
+        for n in 0..=8 {
+            let mut count: Count = [0; 4];
+            for _ in 0..n {
+                add(&mut count, 1);
+            }
+
+            for limit in 0..=20 {
+                assert_eq!(less_than(&count, limit) & 1 == 1, n < limit);
+            }
+        }
+    }
+
+    #[test]
+    fn test_accessible_matches_is_accessable() {
+        // Widths either side of word boundaries
+        for (width, seed) in [(1, 2), (63, 3), (64, 4), (65, 5), (129, 6)] {
+            let grid = generate(7, width, 67, seed);
+            let bits = BitGrid::new(&grid).unwrap();
+
+            for bale_limit in [0, 1, 4, 8, 9] {
+                let mask = bits.accessible(bale_limit);
+
+                for i in 0..grid.len() {
+                    for j in 0..width {
+                        let bit = mask[i * bits.stride + j / WORD_BITS] >> (j % WORD_BITS) & 1;
+                        let expected = is_accessable(
+                            i as isize,
+                            j as isize,
+                            &grid,
+                            bale_limit,
+                            &Neighbourhood::default(),
+                        );
+                        assert_eq!(bit == 1, expected, "width {}, ({}, {})", width, i, j);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_remove_accessible_many_waves() {
+        // Strips are eaten from both ends, so many waves cross word boundaries
+        for size in [7, 64, 200] {
+            let mut grid = strips(size);
+            let mut bits = BitGrid::new(&grid).unwrap();
+
+            let waves = remove_accessable(&mut grid, BALE_LIMIT, &Neighbourhood::default());
+            assert!(waves.iter().flatten().flatten().max() >= Some(&(size / 4)));
+            assert_eq!(bits.remove_accessible(BALE_LIMIT), removed_count(&waves));
+            assert_eq!(bits.to_squares(), grid);
+        }
+    }
+
+    #[test]
+    fn test_remove_accessible_matches_worklist() {
+        for (width, seed) in [(10, 7), (64, 8), (100, 9)] {
+            let mut grid = generate(40, width, 67, seed);
+            let mut bits = BitGrid::new(&grid).unwrap();
+
+            for bale_limit in [3, 4, 5] {
+                let waves = remove_accessable(&mut grid, bale_limit, &Neighbourhood::default());
+                assert_eq!(bits.remove_accessible(bale_limit), removed_count(&waves));
+                assert_eq!(bits.to_squares(), grid);
+            }
+        }
+    }
+}
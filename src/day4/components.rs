@@ -0,0 +1,290 @@
+//! Connected regions of bales and how part B's removal breaks them up.
+//!
+//! Two bales belong to the same component if a path of bales joins them,
+//! each step moving to one of the 4 orthogonal squares or, with
+//! 8-connectivity, to one of the 8 surrounding squares. Components are
+//! numbered from 1 in the order their first square is met reading the grid
+//! row by row.
+
+use std::collections::VecDeque;
+
+use anyhow::{Result, bail};
+
+use super::Square;
+use super::waves::wave_count;
+use crate::params::Params;
+
+/// Which squares are joined to a bale when finding components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// The 4 orthogonal squares
+    Four,
+    /// The 8 surrounding squares
+    Eight,
+}
+
+impl Connectivity {
+    /// Reads the `connectivity` parameter, which is 4 or 8 (the default).
+    pub fn from_params(params: &Params) -> Result<Self> {
+        match params.get_or("connectivity", 8)? {
+            4 => Ok(Connectivity::Four),
+            8 => Ok(Connectivity::Eight),
+            n => bail!("Connectivity [{}] must be 4 or 8", n),
+        }
+    }
+
+    /// The offsets of the joined squares.
+    fn offsets(self) -> &'static [(isize, isize)] {
+        match self {
+            Connectivity::Four => &[(-1, 0), (0, -1), (0, 1), (1, 0)],
+            Connectivity::Eight => &[
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+                (0, -1),
+                (0, 1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+            ],
+        }
+    }
+
+    /// The number of joined squares.
+    fn count(self) -> usize {
+        self.offsets().len()
+    }
+}
+
+/// A connected component of bales.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Component {
+    /// Number of bales in the component
+    pub size: usize,
+    /// First and last rows the component reaches
+    pub rows: (usize, usize),
+    /// First and last columns the component reaches
+    pub columns: (usize, usize),
+}
+
+/// Labels the connected components of bales by flood filling from each
+/// unlabelled bale in reading order.
+///
+/// # Returns
+/// For every square, the index into the returned components of the
+/// component its bale belongs to (`None` for empty squares), together with
+/// the components.
+pub fn label(
+    grid: &[Vec<Square>],
+    connectivity: Connectivity,
+) -> (Vec<Vec<Option<usize>>>, Vec<Component>) {
+    let height = grid.len();
+    let width = grid[0].len();
+
+    let mut labels = vec![vec![None; width]; height];
+    let mut components = vec![];
+    let mut queue = VecDeque::new();
+
+    for i in 0..height {
+        for j in 0..width {
+            if grid[i][j] != Square::Bale || labels[i][j].is_some() {
+                continue;
+            }
+
+            let id = components.len();
+            let mut component = Component {
+                size: 0,
+                rows: (i, i),
+                columns: (j, j),
+            };
+
+            labels[i][j] = Some(id);
+            queue.push_back((i, j));
+
+            while let Some((ci, cj)) = queue.pop_front() {
+                component.size += 1;
+                component.rows = (component.rows.0.min(ci), component.rows.1.max(ci));
+                component.columns = (component.columns.0.min(cj), component.columns.1.max(cj));
+
+                for &(di, dj) in connectivity.offsets() {
+                    let (Some(ni), Some(nj)) =
+                        (ci.checked_add_signed(di), cj.checked_add_signed(dj))
+                    else {
+                        continue;
+                    };
+
+                    if ni < height
+                        && nj < width
+                        && grid[ni][nj] == Square::Bale
+                        && labels[ni][nj].is_none()
+                    {
+                        labels[ni][nj] = Some(id);
+                        queue.push_back((ni, nj));
+                    }
+                }
+            }
+
+            components.push(component);
+        }
+    }
+
+    (labels, components)
+}
+
+/// Describes the components of the starting grid and how many are left
+/// after each wave of part B's removal.
+///
+/// # Arguments
+/// * `grid` - The grid before any bales are removed
+/// * `waves` - The wave in which each bale was removed
+/// * `connectivity` - Which squares are joined to a bale
+///
+/// # Returns
+/// One line per component with its size and bounding box, then one line per
+/// wave with the components and bales remaining once it's gone.
+pub fn report(
+    grid: &[Vec<Square>],
+    waves: &[Vec<Option<usize>>],
+    connectivity: Connectivity,
+) -> String {
+    let (_, components) = label(grid, connectivity);
+
+    let mut out = vec![format!(
+        "{} components ({}-connected)",
+        components.len(),
+        connectivity.count()
+    )];
+
+    for (id, component) in components.iter().enumerate() {
+        out.push(format!(
+            "#{}: {} bales, rows {}-{}, columns {}-{}",
+            id + 1,
+            component.size,
+            component.rows.0,
+            component.rows.1,
+            component.columns.0,
+            component.columns.1
+        ));
+    }
+
+    for wave in 1..=wave_count(waves) {
+        let remaining = remaining_after(grid, waves, wave);
+        let (_, components) = label(&remaining, connectivity);
+        let bales: usize = components.iter().map(|c| c.size).sum();
+
+        out.push(format!(
+            "after wave {}: {} components, {} bales",
+            wave,
+            components.len(),
+            bales
+        ));
+    }
+
+    out.join("\n")
+}
+
+/// Finds the bales still in the pile once `wave` has been removed.
+fn remaining_after(
+    grid: &[Vec<Square>],
+    waves: &[Vec<Option<usize>>],
+    wave: usize,
+) -> Vec<Vec<Square>> {
+    grid.iter()
+        .zip(waves)
+        .map(|(row, row_waves)| {
+            row.iter()
+                .zip(row_waves)
+                .map(|(&square, removed)| match removed {
+                    Some(w) if *w <= wave => Square::Empty,
+                    _ => square,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day4::{BALE_LIMIT, Neighbourhood, parse_grid, remove_accessable};
+
+    fn grid(lines: &[&str]) -> Vec<Vec<Square>> {
+        let lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        parse_grid(&lines, 0).unwrap()
+    }
+
+    #[test]
+    fn test_label_connectivity() {
+        let diagonal = grid(&["@..", ".@.", "..@"]);
+
+        let (labels, components) = label(&diagonal, Connectivity::Four);
+        assert_eq!(components.len(), 3);
+        assert_eq!(labels[2][2], Some(2));
+
+        let (labels, components) = label(&diagonal, Connectivity::Eight);
+        assert_eq!(
+            components,
+            vec![Component {
+                size: 3,
+                rows: (0, 2),
+                columns: (0, 2)
+            }]
+        );
+        assert_eq!(labels[0][1], None);
+    }
+
+    #[test]
+    fn test_label_bounding_boxes() {
+        // A U shape is found from its top-left corner but reaches further
+        // down and right
+        let lines = grid(&["@.@.", "@.@.", "@@@.", "...@"]);
+        let (_, components) = label(&lines, Connectivity::Four);
+
+        assert_eq!(
+            components,
+            vec![
+                Component {
+                    size: 7,
+                    rows: (0, 2),
+                    columns: (0, 2)
+                },
+                Component {
+                    size: 1,
+                    rows: (3, 3),
+                    columns: (3, 3)
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        // The isolated bales go with the block's corners in wave 1, and the
+        // block then wears down from its ends
+        let start = grid(&["@@@@@", "@@@@@", "@@@@@", ".....", "@...@"]);
+        let mut removed = start.clone();
+        let waves = remove_accessable(&mut removed, BALE_LIMIT, &Neighbourhood::default());
+
+        assert_eq!(
+            report(&start, &waves, Connectivity::Eight),
+            "3 components (8-connected)\n\
+             #1: 15 bales, rows 0-2, columns 0-4\n\
+             #2: 1 bales, rows 4-4, columns 0-0\n\
+             #3: 1 bales, rows 4-4, columns 4-4\n\
+             after wave 1: 1 components, 11 bales\n\
+             after wave 2: 1 components, 9 bales\n\
+             after wave 3: 1 components, 5 bales\n\
+             after wave 4: 1 components, 1 bales\n\
+             after wave 5: 0 components, 0 bales"
+        );
+    }
+
+    #[test]
+    fn test_from_params() {
+        let parse = |args: &[&str]| Connectivity::from_params(&Params::from_strs(args));
+
+        assert_eq!(parse(&[]).unwrap(), Connectivity::Eight);
+        assert_eq!(parse(&["connectivity=4"]).unwrap(), Connectivity::Four);
+        assert!(parse(&["connectivity=6"]).is_err());
+    }
+}
@@ -0,0 +1,348 @@
+//! Stacked bale grids, where bales also lean on the layers above and below.
+//!
+//! Layered input is a series of 2D slices separated by blank lines, bottom
+//! layer first. Every bale has up to 26 neighbours: the 8 around it in its
+//! own layer and the 9 squares in the same positions directly above and
+//! below. Everything beyond the stack's edges is empty.
+
+use std::collections::VecDeque;
+
+use anyhow::{Result, bail};
+
+use super::{Square, read_grid};
+
+/// A stack of equally sized layers, indexed by layer, row and column.
+pub type Layers = Vec<Vec<Vec<Square>>>;
+
+/// Splits the input into slices at blank lines, skipping any run of blank
+/// lines so that leading, trailing or repeated blank lines don't add empty
+/// slices.
+///
+/// # Returns
+/// Each slice together with the index in `lines` of its first line.
+pub fn split_slices(lines: &[String]) -> Vec<(usize, &[String])> {
+    let mut first_line = 0;
+
+    lines
+        .split(|l| l.trim().is_empty())
+        .filter_map(|slice| {
+            let start = first_line;
+            first_line += slice.len() + 1;
+
+            (!slice.is_empty()).then_some((start, slice))
+        })
+        .collect()
+}
+
+/// Parses slices into layers, checking they all have the same size.
+///
+/// # Returns
+/// The layers, or an error naming every bad square and ragged row, and every
+/// layer whose size differs from the first one's.
+pub fn parse_layers(slices: &[(usize, &[String])]) -> Result<Layers> {
+    let size = |layer: &[Vec<Square>]| (layer.len(), layer[0].len());
+
+    let mut layers: Layers = vec![];
+    let mut errors = vec![];
+
+    for (l, &(first_line, slice)) in slices.iter().enumerate() {
+        let before = errors.len();
+        let layer = read_grid(slice, first_line, &mut errors);
+
+        let (h, w) = size(&layer);
+        let (height, width) = layers.first().map_or((h, w), |first| size(first));
+
+        // A layer with bad rows has no meaningful size to compare
+        if errors.len() == before && (h, w) != (height, width) {
+            errors.push(format!(
+                "layer {}: is {}x{} but layer 1 is {}x{}",
+                l + 1,
+                h,
+                w,
+                height,
+                width
+            ));
+        }
+
+        layers.push(layer);
+    }
+
+    if !errors.is_empty() {
+        bail!("Invalid layers:\n{}", errors.join("\n"));
+    }
+
+    Ok(layers)
+}
+
+/// Finds the neighbours of (k, i, j) among the 26 surrounding squares that
+/// lie inside the stack.
+fn neighbours(
+    k: usize,
+    i: usize,
+    j: usize,
+    layers: &[Vec<Vec<Square>>],
+) -> Vec<(usize, usize, usize)> {
+    let (depth, height, width) = (layers.len(), layers[0].len(), layers[0][0].len());
+    let mut found = Vec::with_capacity(26);
+
+    for dk in -1..=1 {
+        for di in -1..=1 {
+            for dj in -1..=1 {
+                if (dk, di, dj) == (0, 0, 0) {
+                    continue;
+                }
+
+                let (Some(nk), Some(ni), Some(nj)) = (
+                    k.checked_add_signed(dk),
+                    i.checked_add_signed(di),
+                    j.checked_add_signed(dj),
+                ) else {
+                    continue;
+                };
+
+                if nk < depth && ni < height && nj < width {
+                    found.push((nk, ni, nj));
+                }
+            }
+        }
+    }
+
+    found
+}
+
+/// Counts the bale neighbours of every square in the stack.
+fn neighbour_counts(layers: &[Vec<Vec<Square>>]) -> Vec<Vec<Vec<usize>>> {
+    let mut counts: Vec<Vec<Vec<usize>>> = layers
+        .iter()
+        .map(|layer| vec![vec![0; layer[0].len()]; layer.len()])
+        .collect();
+
+    for (k, layer) in layers.iter().enumerate() {
+        for (i, row) in layer.iter().enumerate() {
+            for (j, square) in row.iter().enumerate() {
+                if *square == Square::Bale {
+                    for (nk, ni, nj) in neighbours(k, i, j, layers) {
+                        counts[nk][ni][nj] += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    counts
+}
+
+/// Counts the bales with fewer than `bale_limit` bale neighbours, as in
+/// part A.
+pub fn accessible_count(layers: &[Vec<Vec<Square>>], bale_limit: usize) -> usize {
+    let counts = neighbour_counts(layers);
+
+    layers
+        .iter()
+        .flatten()
+        .flatten()
+        .zip(counts.iter().flatten().flatten())
+        .filter(|&(square, &count)| *square == Square::Bale && count < bale_limit)
+        .count()
+}
+
+/// Repeatedly removes accessible bales until none are left, as in part B.
+///
+/// Uses the same first-in first-out worklist as the flat grid's removal:
+/// removing a bale lowers its neighbours' counts, and a bale is queued as
+/// soon as its count drops below `bale_limit`.
+///
+/// # Arguments
+/// * `layers` - The stack; removed bales are set to empty
+/// * `bale_limit` - Maximum number of neighbours for a bale to be accessible
+///
+/// # Returns
+/// The number of bales removed.
+pub fn remove_accessible(layers: &mut [Vec<Vec<Square>>], bale_limit: usize) -> usize {
+    let mut counts = neighbour_counts(layers);
+    let mut queue = VecDeque::new();
+
+    for (k, layer) in layers.iter_mut().enumerate() {
+        for (i, row) in layer.iter_mut().enumerate() {
+            for (j, square) in row.iter_mut().enumerate() {
+                if *square == Square::Bale && counts[k][i][j] < bale_limit {
+                    // Queued bales are marked empty so they're only queued once
+                    *square = Square::Empty;
+                    queue.push_back((k, i, j));
+                }
+            }
+        }
+    }
+
+    let mut removed = 0;
+
+    while let Some((k, i, j)) = queue.pop_front() {
+        removed += 1;
+
+        for (nk, ni, nj) in neighbours(k, i, j, layers) {
+            counts[nk][ni][nj] -= 1;
+
+            if layers[nk][ni][nj] == Square::Bale && counts[nk][ni][nj] < bale_limit {
+                layers[nk][ni][nj] = Square::Empty;
+                queue.push_back((nk, ni, nj));
+            }
+        }
+    }
+
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day4::bits::{generate, xorshift};
+    use crate::day4::neighbourhood::Neighbourhood;
+    use crate::day4::{BALE_LIMIT, is_accessable, parse_grid, remove_accessable, removed_count};
+
+    fn to_lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|l| l.to_string()).collect()
+    }
+
+    fn layers(text: &[&str]) -> Layers {
+        let lines = to_lines(text);
+        parse_layers(&split_slices(&lines)).unwrap()
+    }
+
+    #[test]
+    fn test_split_slices() {
+        let lines = to_lines(&["", "@.", "", "", ".@", ""]);
+        assert_eq!(
+            split_slices(&lines),
+            vec![(1, &lines[1..2]), (4, &lines[4..5])]
+        );
+    }
+
+    #[test]
+    fn test_parse_layers_mismatched() {
+        let lines = to_lines(&["@@", "@@", "", "@@", "", "@@@", "@@@", "", "@@", "@"]);
+        let err = parse_layers(&split_slices(&lines)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid layers:\nlayer 2: is 1x2 but layer 1 is 2x2\n\
+             layer 3: is 2x3 but layer 1 is 2x2\nline 10: is 1 wide but line 9 is 2"
+        );
+    }
+
+    #[test]
+    fn test_neighbour_counts_cube() {
+        let cube = layers(&[
+            "@@@", "@@@", "@@@", "", "@@@", "@@@", "@@@", "", "@@@", "@@@", "@@@",
+        ]);
+        let counts = neighbour_counts(&cube);
+
+        assert_eq!(counts[1][1][1], 26);
+        assert_eq!(counts[0][0][0], 7);
+        assert_eq!(counts[0][1][1], 17);
+        assert_eq!(counts[1][0][1], 17);
+    }
+
+    #[test]
+    fn test_cube() {
+        let cube = layers(&[
+            "@@@", "@@@", "@@@", "", "@@@", "@@@", "@@@", "", "@@@", "@@@", "@@@",
+        ]);
+
+        // Corners have 7 neighbours, edges 11, faces 17 and the centre 26
+        assert_eq!(accessible_count(&cube, BALE_LIMIT), 0);
+        assert_eq!(accessible_count(&cube, 8), 8);
+        assert_eq!(accessible_count(&cube, 12), 20);
+
+        // Once corners and edges go, faces are left with 9 and the centre
+        // with 6
+        assert_eq!(remove_accessible(&mut cube.clone(), 12), 27);
+        // Losing the corners leaves each edge with 9
+        assert_eq!(remove_accessible(&mut cube.clone(), 8), 8);
+        assert_eq!(remove_accessible(&mut cube.clone(), 7), 0);
+    }
+
+    #[test]
+    fn test_single_layer_matches_flat_grid() {
+        let lines = to_lines(&[
+            "..@@.@@@@.",
+            "@@@.@.@.@@",
+            "@@@@@.@.@@",
+            "@.@@@@..@.",
+            "@@.@@@@.@@",
+            ".@@@@@@@.@",
+            ".@.@.@.@@@",
+            "@.@@@.@@@@",
+            ".@@@@@@@@.",
+            "@.@.@@@.@.",
+        ]);
+
+        for bale_limit in 1..=8 {
+            let mut stack = parse_layers(&[(0, &lines)]).unwrap();
+            let mut grid = parse_grid(&lines, 0).unwrap();
+
+            assert_eq!(
+                accessible_count(&stack, bale_limit),
+                grid.iter()
+                    .enumerate()
+                    .flat_map(|(i, row)| (0..row.len()).map(move |j| (i, j)))
+                    .filter(|&(i, j)| is_accessable(
+                        i as isize,
+                        j as isize,
+                        &grid,
+                        bale_limit,
+                        &Neighbourhood::default()
+                    ))
+                    .count()
+            );
+
+            let waves = remove_accessable(&mut grid, bale_limit, &Neighbourhood::default());
+            assert_eq!(
+                remove_accessible(&mut stack, bale_limit),
+                removed_count(&waves)
+            );
+            assert_eq!(stack[0], grid);
+        }
+    }
+
+    #[test]
+    fn test_remove_accessible_matches_rescanning() {
+        let mut state: u64 = 0x2545f4914f6cdd1d;
+
+        for _ in 0..30 {
+            let depth = (xorshift(&mut state) % 5 + 1) as usize;
+            let height = (xorshift(&mut state) % 6 + 1) as usize;
+            let width = (xorshift(&mut state) % 6 + 1) as usize;
+            let bale_limit = (xorshift(&mut state) % 27) as usize;
+
+            let mut stack: Layers = (0..depth)
+                .map(|_| generate(height, width, 75, xorshift(&mut state)))
+                .collect();
+
+            // Remove every accessible bale a wave at a time until none are
+            let mut rescanned = stack.clone();
+            let mut expected = 0;
+            loop {
+                let counts = neighbour_counts(&rescanned);
+                let mut wave = 0;
+
+                for (k, layer) in rescanned.iter_mut().enumerate() {
+                    for (i, row) in layer.iter_mut().enumerate() {
+                        for (j, square) in row.iter_mut().enumerate() {
+                            if *square == Square::Bale && counts[k][i][j] < bale_limit {
+                                *square = Square::Empty;
+                                wave += 1;
+                            }
+                        }
+                    }
+                }
+
+                if wave == 0 {
+                    break;
+                }
+                expected += wave;
+            }
+
+            assert_eq!(remove_accessible(&mut stack, bale_limit), expected);
+            assert_eq!(stack, rescanned);
+        }
+    }
+}
@@ -0,0 +1,296 @@
+//! Neighbourhoods deciding which squares count as a bale's neighbours.
+//!
+//! The puzzle uses the 8 surrounding squares (the Moore neighbourhood of
+//! radius 1) and treats everything beyond the grid's edge as empty. Either
+//! can be changed through day4's parameters.
+
+use anyhow::{Result, bail};
+
+use crate::params::Params;
+
+/// The shape of a neighbourhood.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    /// Squares within `radius` steps counting only orthogonal moves
+    /// (the 4 orthogonal squares for radius 1)
+    VonNeumann { radius: usize },
+    /// Squares within `radius` steps counting diagonal moves
+    /// (the 8 surrounding squares for radius 1)
+    Moore { radius: usize },
+    /// The 6 neighbours of a hexagonal grid stored in "odd-r" layout, where
+    /// every odd row is shifted half a square to the right
+    Hexagonal,
+}
+
+/// A neighbourhood shape together with how the grid's edges behave.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Neighbourhood {
+    /// The neighbourhood's shape
+    shape: Shape,
+    /// Whether the grid wraps around at its edges like a torus
+    wrap: bool,
+    /// Neighbour offsets for even and odd rows, which only differ for
+    /// hexagonal grids
+    offsets: [Vec<(isize, isize)>; 2],
+}
+
+impl Default for Neighbourhood {
+    fn default() -> Self {
+        Neighbourhood::new(Shape::Moore { radius: 1 }, false)
+    }
+}
+
+impl Neighbourhood {
+    /// Creates a neighbourhood of the given shape.
+    ///
+    /// # Arguments
+    /// * `shape` - The neighbourhood's shape
+    /// * `wrap` - Whether the grid wraps around at its edges
+    pub fn new(shape: Shape, wrap: bool) -> Self {
+        let offsets = match shape {
+            Shape::VonNeumann { radius } => {
+                let offsets =
+                    square_offsets(radius, |di, dj| di.abs() + dj.abs() <= radius as isize);
+                [offsets.clone(), offsets]
+            }
+            Shape::Moore { radius } => {
+                let offsets = square_offsets(radius, |_, _| true);
+                [offsets.clone(), offsets]
+            }
+            Shape::Hexagonal => [
+                vec![(0, -1), (0, 1), (-1, -1), (-1, 0), (1, -1), (1, 0)],
+                vec![(0, -1), (0, 1), (-1, 0), (-1, 1), (1, 0), (1, 1)],
+            ],
+        };
+
+        Neighbourhood {
+            shape,
+            wrap,
+            offsets,
+        }
+    }
+
+    /// Reads the neighbourhood from the `neighbourhood`, `radius` and
+    /// `wrap` parameters, defaulting to the puzzle's Moore neighbourhood of
+    /// radius 1 without wrapping.
+    pub fn from_params(params: &Params) -> Result<Self> {
+        let name: String = params.get_or("neighbourhood", "moore".to_string())?;
+        let radius: Option<usize> = params.get("radius")?;
+        let wrap = params.get_or("wrap", false)?;
+
+        if radius == Some(0) {
+            bail!("Parameter [radius] must be at least 1");
+        }
+
+        let shape = match name.as_str() {
+            "moore" => Shape::Moore {
+                radius: radius.unwrap_or(1),
+            },
+            "von-neumann" => Shape::VonNeumann {
+                radius: radius.unwrap_or(1),
+            },
+            "hex" => {
+                if radius.is_some() {
+                    bail!("Parameter [radius] doesn't apply to hexagonal neighbourhoods");
+                }
+                Shape::Hexagonal
+            }
+            _ => bail!("Neighbourhood [{}] must be moore, von-neumann or hex", name),
+        };
+
+        Ok(Neighbourhood::new(shape, wrap))
+    }
+
+    /// Checks that the neighbourhood can be used on a grid of this size.
+    ///
+    /// Wrapping a hexagonal grid joins its last row to its first, so the
+    /// odd-r layout only lines up if there are an even number of rows.
+    pub fn check(&self, height: usize) -> Result<()> {
+        if self.wrap && self.shape == Shape::Hexagonal && !height.is_multiple_of(2) {
+            bail!(
+                "Wrapping a hexagonal grid needs an even number of rows, not {}",
+                height
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Finds the neighbours of (i, j) in a grid of the given size.
+    ///
+    /// Without wrapping, offsets that leave the grid are skipped. With
+    /// wrapping they continue from the opposite edge; on grids too small
+    /// for the neighbourhood, a square reached several ways is only listed
+    /// once and a square is never its own neighbour.
+    pub fn neighbours(
+        &self,
+        i: usize,
+        j: usize,
+        height: usize,
+        width: usize,
+    ) -> Vec<(usize, usize)> {
+        let offsets = &self.offsets[i % 2];
+
+        if !self.wrap {
+            return offsets
+                .iter()
+                .filter_map(|&(di, dj)| {
+                    let ni = i.checked_add_signed(di)?;
+                    let nj = j.checked_add_signed(dj)?;
+
+                    (ni < height && nj < width).then_some((ni, nj))
+                })
+                .collect();
+        }
+
+        let mut found = Vec::with_capacity(offsets.len());
+
+        for &(di, dj) in offsets {
+            let ni = (i as isize + di).rem_euclid(height as isize) as usize;
+            let nj = (j as isize + dj).rem_euclid(width as isize) as usize;
+
+            if (ni, nj) != (i, j) && !found.contains(&(ni, nj)) {
+                found.push((ni, nj));
+            }
+        }
+
+        found
+    }
+}
+
+/// Lists the offsets within a square of the given radius, excluding the
+/// centre, that satisfy `keep`.
+fn square_offsets(radius: usize, keep: impl Fn(isize, isize) -> bool) -> Vec<(isize, isize)> {
+    let r = radius as isize;
+    let mut offsets = vec![];
+
+    for di in -r..=r {
+        for dj in -r..=r {
+            if (di, dj) != (0, 0) && keep(di, dj) {
+                offsets.push((di, dj));
+            }
+        }
+    }
+
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut neighbours: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+        neighbours.sort();
+        neighbours
+    }
+
+    #[test]
+    fn test_moore() {
+        let n = Neighbourhood::default();
+        assert_eq!(n.neighbours(2, 2, 5, 5).len(), 8);
+        assert_eq!(
+            sorted(n.neighbours(0, 0, 5, 5)),
+            vec![(0, 1), (1, 0), (1, 1)]
+        );
+    }
+
+    #[test]
+    fn test_moore_radius() {
+        let n = Neighbourhood::new(Shape::Moore { radius: 2 }, false);
+        assert_eq!(n.neighbours(2, 2, 5, 5).len(), 24);
+        assert_eq!(n.neighbours(0, 0, 5, 5).len(), 8);
+    }
+
+    #[test]
+    fn test_von_neumann() {
+        let n = Neighbourhood::new(Shape::VonNeumann { radius: 1 }, false);
+        assert_eq!(
+            sorted(n.neighbours(1, 1, 3, 3)),
+            vec![(0, 1), (1, 0), (1, 2), (2, 1)]
+        );
+
+        let n = Neighbourhood::new(Shape::VonNeumann { radius: 2 }, false);
+        assert_eq!(n.neighbours(2, 2, 5, 5).len(), 12);
+    }
+
+    #[test]
+    fn test_hexagonal() {
+        let n = Neighbourhood::new(Shape::Hexagonal, false);
+        // Even rows reach up-left and down-left
+        assert_eq!(
+            sorted(n.neighbours(2, 2, 5, 5)),
+            vec![(1, 1), (1, 2), (2, 1), (2, 3), (3, 1), (3, 2)]
+        );
+        // Odd rows reach up-right and down-right
+        assert_eq!(
+            sorted(n.neighbours(1, 2, 5, 5)),
+            vec![(0, 2), (0, 3), (1, 1), (1, 3), (2, 2), (2, 3)]
+        );
+    }
+
+    #[test]
+    fn test_hexagonal_is_symmetric() {
+        let n = Neighbourhood::new(Shape::Hexagonal, true);
+        for i in 0..6 {
+            for j in 0..5 {
+                for (ni, nj) in n.neighbours(i, j, 6, 5) {
+                    assert!(n.neighbours(ni, nj, 6, 5).contains(&(i, j)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_wrap() {
+        let n = Neighbourhood::new(Shape::Moore { radius: 1 }, true);
+        assert_eq!(
+            sorted(n.neighbours(0, 0, 4, 4)),
+            vec![
+                (0, 1),
+                (0, 3),
+                (1, 0),
+                (1, 1),
+                (1, 3),
+                (3, 0),
+                (3, 1),
+                (3, 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrap_small_grid() {
+        // On a 2x2 torus every other square is reached, once each
+        let n = Neighbourhood::new(Shape::Moore { radius: 1 }, true);
+        assert_eq!(
+            sorted(n.neighbours(0, 0, 2, 2)),
+            vec![(0, 1), (1, 0), (1, 1)]
+        );
+    }
+
+    #[test]
+    fn test_check_hexagonal_wrap() {
+        let n = Neighbourhood::new(Shape::Hexagonal, true);
+        assert!(n.check(4).is_ok());
+        assert!(n.check(5).is_err());
+        assert!(Neighbourhood::new(Shape::Hexagonal, false).check(5).is_ok());
+    }
+
+    #[test]
+    fn test_from_params() {
+        let parse = |args: &[&str]| Neighbourhood::from_params(&Params::from_strs(args));
+
+        assert_eq!(parse(&[]).unwrap(), Neighbourhood::default());
+        assert_eq!(
+            parse(&["neighbourhood=von-neumann", "radius=2", "wrap"]).unwrap(),
+            Neighbourhood::new(Shape::VonNeumann { radius: 2 }, true)
+        );
+        assert_eq!(
+            parse(&["neighbourhood=hex"]).unwrap(),
+            Neighbourhood::new(Shape::Hexagonal, false)
+        );
+        assert!(parse(&["neighbourhood=hex", "radius=2"]).is_err());
+        assert!(parse(&["radius=0"]).is_err());
+        assert!(parse(&["neighbourhood=triangle"]).is_err());
+    }
+}
@@ -0,0 +1,256 @@
+//! Visualisations of the removal waves computed for part B.
+//!
+//! Every view is built from the grid left after removal (where only the
+//! bales that were never removed remain) together with the wave in which
+//! each removed bale went. Frame `k` shows the pile as wave `k` is removed;
+//! frame 0 is the untouched pile.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use super::Square;
+
+/// The state of a square in a single frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    /// Never held a bale
+    Empty,
+    /// A bale still in the pile
+    Bale,
+    /// A bale removed in this frame's wave
+    Removing,
+    /// A bale removed in an earlier wave
+    Removed,
+}
+
+impl Cell {
+    /// Finds a square's state in the given frame.
+    fn at(square: &Square, wave: Option<usize>, frame: usize) -> Self {
+        match wave {
+            Some(w) if w > frame => Cell::Bale,
+            Some(w) if w == frame => Cell::Removing,
+            Some(_) => Cell::Removed,
+            None if *square == Square::Bale => Cell::Bale,
+            None => Cell::Empty,
+        }
+    }
+
+    /// The cell's colour in PPM frames.
+    fn rgb(self) -> [u8; 3] {
+        match self {
+            Cell::Empty => [40, 40, 40],
+            Cell::Bale => [218, 165, 32],
+            Cell::Removing => [220, 20, 60],
+            Cell::Removed => [0, 0, 0],
+        }
+    }
+
+    /// The cell drawn for the terminal, with ANSI colours.
+    fn ansi(self) -> &'static str {
+        match self {
+            Cell::Empty => ".",
+            Cell::Bale => "\x1b[33m@\x1b[0m",
+            Cell::Removing => "\x1b[1;31m@\x1b[0m",
+            Cell::Removed => "\x1b[31m.\x1b[0m",
+        }
+    }
+}
+
+/// Finds the last wave in which any bale was removed.
+pub fn wave_count(waves: &[Vec<Option<usize>>]) -> usize {
+    waves.iter().flatten().flatten().copied().max().unwrap_or(0)
+}
+
+/// Renders the grid with the wave number of every removed bale.
+///
+/// Numbers are right-aligned to the width of the largest wave and cells are
+/// separated by spaces. Bales that were never removed are `@`, and empty
+/// squares are `.`.
+pub fn render_map(grid: &[Vec<Square>], waves: &[Vec<Option<usize>>]) -> String {
+    let width = wave_count(waves).to_string().len();
+
+    grid.iter()
+        .zip(waves)
+        .map(|(row, row_waves)| {
+            row.iter()
+                .zip(row_waves)
+                .map(|(square, wave)| {
+                    let cell = match (wave, square) {
+                        (Some(w), _) => w.to_string(),
+                        (None, Square::Bale) => "@".to_string(),
+                        (None, Square::Empty) => ".".to_string(),
+                    };
+                    format!("{:>width$}", cell)
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Renders a single frame with ANSI colours.
+fn render_frame(grid: &[Vec<Square>], waves: &[Vec<Option<usize>>], frame: usize) -> String {
+    grid.iter()
+        .zip(waves)
+        .map(|(row, row_waves)| {
+            row.iter()
+                .zip(row_waves)
+                .map(|(square, &wave)| Cell::at(square, wave, frame).ansi())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Plays the removal in the terminal, one frame per wave.
+///
+/// # Arguments
+/// * `grid` - The grid left after removal
+/// * `waves` - The wave in which each bale was removed
+/// * `delay` - How long each frame is shown
+///
+/// # Returns
+/// A summary line to print once the animation ends.
+pub fn animate(grid: &[Vec<Square>], waves: &[Vec<Option<usize>>], delay: Duration) -> String {
+    let count = wave_count(waves);
+    let mut stdout = io::stdout();
+
+    for frame in 0..=count {
+        // Clear the screen and move the cursor home before each frame
+        print!("\x1b[2J\x1b[H");
+        println!("{}", render_frame(grid, waves, frame));
+        println!("wave {}/{}", frame, count);
+        stdout.flush().unwrap();
+
+        thread::sleep(delay);
+    }
+
+    format!("{} waves", count)
+}
+
+/// Encodes a single frame as a binary PPM image.
+///
+/// # Arguments
+/// * `grid` - The grid left after removal
+/// * `waves` - The wave in which each bale was removed
+/// * `frame` - The frame to draw
+/// * `scale` - Width and height of each square in pixels
+fn ppm(grid: &[Vec<Square>], waves: &[Vec<Option<usize>>], frame: usize, scale: usize) -> Vec<u8> {
+    let height = grid.len() * scale;
+    let width = grid[0].len() * scale;
+
+    let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+
+    for (row, row_waves) in grid.iter().zip(waves) {
+        let pixels: Vec<u8> = row
+            .iter()
+            .zip(row_waves)
+            .flat_map(|(square, &wave)| {
+                let rgb = Cell::at(square, wave, frame).rgb();
+                rgb.repeat(scale)
+            })
+            .collect();
+
+        for _ in 0..scale {
+            image.extend_from_slice(&pixels);
+        }
+    }
+
+    image
+}
+
+/// Writes every frame as `frame_NNNN.ppm` into a directory, creating it if
+/// needed.
+///
+/// # Arguments
+/// * `grid` - The grid left after removal
+/// * `waves` - The wave in which each bale was removed
+/// * `dir` - Directory to write the frames to
+/// * `scale` - Width and height of each square in pixels
+///
+/// # Returns
+/// The number of frames written.
+pub fn write_frames(
+    grid: &[Vec<Square>],
+    waves: &[Vec<Option<usize>>],
+    dir: &Path,
+    scale: usize,
+) -> Result<usize> {
+    fs::create_dir_all(dir)?;
+
+    let count = wave_count(waves);
+
+    for frame in 0..=count {
+        let path = dir.join(format!("frame_{:04}.ppm", frame));
+        fs::write(path, ppm(grid, waves, frame, scale))?;
+    }
+
+    Ok(count + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day4::{BALE_LIMIT, Neighbourhood, parse_grid, remove_accessable};
+
+    fn removed(lines: &[&str]) -> (Vec<Vec<Square>>, Vec<Vec<Option<usize>>>) {
+        let lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        let mut grid = parse_grid(&lines, 0).unwrap();
+        let waves = remove_accessable(&mut grid, BALE_LIMIT, &Neighbourhood::default());
+        (grid, waves)
+    }
+
+    #[test]
+    fn test_render_map() {
+        let (grid, waves) = removed(&["@@@", "@@@", "@@@", "..."]);
+        assert_eq!(render_map(&grid, &waves), "1 2 1\n2 3 2\n1 2 1\n. . .");
+    }
+
+    #[test]
+    fn test_render_map_remaining_bales() {
+        // Only the corners go: the squares beside them are left with 4
+        let (grid, waves) = removed(&["@@@@@", "@@@@@", "@@@@@", "@@@@@", "@@@@@"]);
+        assert_eq!(
+            render_map(&grid, &waves),
+            "1 @ @ @ 1\n@ @ @ @ @\n@ @ @ @ @\n@ @ @ @ @\n1 @ @ @ 1"
+        );
+    }
+
+    #[test]
+    fn test_cell_at() {
+        assert_eq!(Cell::at(&Square::Empty, Some(2), 1), Cell::Bale);
+        assert_eq!(Cell::at(&Square::Empty, Some(2), 2), Cell::Removing);
+        assert_eq!(Cell::at(&Square::Empty, Some(2), 3), Cell::Removed);
+        assert_eq!(Cell::at(&Square::Bale, None, 3), Cell::Bale);
+        assert_eq!(Cell::at(&Square::Empty, None, 0), Cell::Empty);
+    }
+
+    #[test]
+    fn test_render_frame() {
+        let (grid, waves) = removed(&["@.@"]);
+        assert_eq!(
+            render_frame(&grid, &waves, 1),
+            "\x1b[1;31m@\x1b[0m.\x1b[1;31m@\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_ppm() {
+        let (grid, waves) = removed(&["@."]);
+        let image = ppm(&grid, &waves, 0, 2);
+
+        let header = b"P6\n4 2\n255\n";
+        assert_eq!(&image[..header.len()], header);
+
+        let bale = Cell::Bale.rgb();
+        let empty = Cell::Empty.rgb();
+        let row = [bale, bale, empty, empty].concat();
+        assert_eq!(&image[header.len()..], [row.clone(), row].concat());
+    }
+}
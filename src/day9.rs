@@ -6,6 +6,11 @@
 //! ## Input Format
 //! Each line contains comma-separated 2D coordinates: `x,y`.
 //!
+//! The tiles form a closed loop. Further loops may follow, each separated by
+//! a blank line; these are holes (e.g. pillars) cut out of the first loop.
+//! Tiles strictly inside a hole are invalid, but its boundary tiles are not,
+//! and a rectangle may not enclose any invalid hole tile.
+//!
 //! ## Part A
 //! Finds the maximum rectangle area formed by any two points as opposite
 //! corners, considering all point pairs.
@@ -13,84 +18,369 @@
 //! ## Part B
 //! Finds the maximum rectangle area where the entire perimeter lies within
 //! a valid boundary defined by the input points (polygon interior check).
+//!
+//! ## Modes
+//! * `atlas-stats` - Reports the loop's shoelace area and its boundary and
+//!   interior lattice point counts (via Pick's theorem).
+//! * `contained` - Solves part B requiring every tile of the rectangle, not
+//!   just its perimeter, to be inside the loop.
+//! * `explain` - Prints both answers along with the corner tiles (and their
+//!   input line numbers) of the winning rectangles.
+//! * `general` - Solves part B treating the tiles as a general lattice
+//!   polygon, so edges may be diagonal.
+//! * `svg` - Writes an SVG of the tile loop and the best rectangle to the
+//!   path given by `out`. `top=N` also draws the N-1 runners-up in fading
+//!   colours and `size` sets the canvas size in pixels (default 800).
+//! * `top` - Lists the `k` largest valid rectangles (default 10) with their
+//!   corners, largest first.
+//! * `validate` - Reports problems with the tile loop (duplicate vertices,
+//!   zero-length or diagonal edges, collinear points, self-intersections).
 
 use crate::day::Day;
+use crate::params::Params;
+use anyhow::{Result, bail};
 use rayon::prelude::*;
+use std::fmt;
+
+mod coverage;
+mod polygon;
+mod svg;
+mod validation;
+
+use coverage::Coverage;
+
+pub use polygon::{Orientation, Polygon, orientation};
+pub use validation::{Defect, validate, validate_loops, validate_simple};
 
 /// Solution for Day 9: Maximum Rectangle puzzle.
 pub struct Day9 {}
 
 impl Day for Day9 {
     fn part_a(lines: &[String]) -> String {
-        let points: Vec<_> = lines
-            .into_iter()
-            .map(|s| {
-                let mut nums = s.split(',').map(|n| n.parse::<i64>().unwrap());
-                Point {
-                    x: nums.next().unwrap(),
-                    y: nums.next().unwrap(),
+        Day9::largest_rectangle(lines).area.to_string()
+    }
+
+    fn part_b(lines: &[String]) -> String {
+        Day9::largest_valid_rectangle(lines)
+            .unwrap()
+            .area
+            .to_string()
+    }
+
+    fn mode(lines: &[String], mode: &str, params: &Params) -> Result<String> {
+        match mode {
+            "contained" => Ok(Day9::largest_contained_rectangle(lines)?.area.to_string()),
+            "explain" => Ok(format!(
+                "a: {}\nb: {}",
+                Day9::largest_rectangle(lines),
+                Day9::largest_valid_rectangle(lines)?
+            )),
+            "atlas-stats" => Day9::atlas_stats(lines),
+            "general" => Ok(Day9::largest_valid_rectangle_general(lines)?
+                .area
+                .to_string()),
+            "svg" => {
+                let out: String = params.require("out")?;
+                let top = params.get_or("top", 1)?;
+                let size = params.get_or("size", 800)?;
+
+                let svg = Day9::render_svg(lines, top, size)?;
+                std::fs::write(&out, svg)?;
+
+                Ok(format!("wrote {}", out))
+            }
+            "top" => {
+                let k = params.get_or("k", 10)?;
+
+                let rects = Day9::largest_valid_rectangles(lines, k)?;
+
+                Ok(rects
+                    .iter()
+                    .enumerate()
+                    .map(|(i, r)| format!("{}: {}", i + 1, r))
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+            }
+            "validate" => {
+                let defects = validate_loops(&parse_loops(lines), true);
+
+                if defects.is_empty() {
+                    return Ok("valid".to_string());
                 }
-            })
-            .collect();
 
-        points
+                Ok(defect_report(&defects))
+            }
+            _ => bail!("Mode [{}] isn't supported by day9", mode),
+        }
+    }
+}
+
+impl Day9 {
+    /// Finds the largest rectangle formed by any two red tiles (part A).
+    pub fn largest_rectangle(lines: &[String]) -> Rectangle {
+        let tiles: Vec<RedTile> = parse_loops(lines).concat();
+
+        tiles
             .iter()
             .enumerate()
-            .flat_map(|(i, p)| (&points[i..]).iter().map(|op| p.area(*op)))
-            .filter(|a| *a > 0)
-            .max()
+            .flat_map(|(i, t)| tiles[i..].iter().map(|ot| Rectangle::new(*t, *ot)))
+            .max_by_key(|r| r.area)
             .unwrap()
-            .to_string()
     }
 
-    fn part_b(lines: &[String]) -> String {
-        let points: Vec<_> = lines
+    /// Finds the largest rectangle whose perimeter lies inside the polygon (part B).
+    ///
+    /// The tiles are [validated](validate_loops) first; any defect in the
+    /// loops is reported as an error rather than searched over. This is the
+    /// first of [`Day9::largest_valid_rectangles`], so ties are broken the
+    /// same way in every mode.
+    pub fn largest_valid_rectangle(lines: &[String]) -> Result<Rectangle> {
+        Ok(Day9::largest_valid_rectangles(lines, 1)?
             .into_iter()
-            .map(|s| {
-                let mut nums = s.split(',').map(|n| n.parse::<i64>().unwrap());
-                Point {
-                    x: nums.next().unwrap(),
-                    y: nums.next().unwrap(),
-                }
-            })
+            .next()
+            .unwrap())
+    }
+
+    /// Finds the largest rectangle lying entirely inside the polygon.
+    ///
+    /// Unlike [`Day9::largest_valid_rectangle`], which only checks the
+    /// perimeter, every tile of the rectangle must be inside, so pockets of
+    /// outside tiles that the perimeter straddles rule a rectangle out.
+    pub fn largest_contained_rectangle(lines: &[String]) -> Result<Rectangle> {
+        let loops = parse_valid_loops(lines, true)?;
+        let tiles = loops.concat();
+
+        let points: Vec<Point> = tiles.iter().map(|t| t.point).collect();
+        let world = Atlas::from_loops(&loops);
+        let coverage = Coverage::new(&points, &world);
+
+        Ok(tiles
+            .par_iter()
+            .enumerate()
+            .flat_map_iter(|(i, t)| tiles[i..].iter().map(move |ot| Rectangle::new(*t, *ot)))
+            .filter(|r| coverage.contains(&r.square()))
+            .max_by_key(|r| r.area)
+            .unwrap())
+    }
+
+    /// Finds the `k` largest rectangles whose perimeters lie inside the polygon.
+    ///
+    /// # Returns
+    /// Up to `k` rectangles in descending order of area.
+    pub fn largest_valid_rectangles(lines: &[String], k: usize) -> Result<Vec<Rectangle>> {
+        let loops = parse_valid_loops(lines, true)?;
+
+        let world = Atlas::from_loops(&loops);
+
+        Ok(world.largest_rectangles(&loops.concat(), k))
+    }
+
+    /// Like [`Day9::largest_valid_rectangle`], but treats the tiles as a
+    /// general [`Polygon`] so edges may run in any direction.
+    ///
+    /// Holes aren't supported here; the input must be a single loop. Each
+    /// side of a candidate is checked against the polygon's edges with
+    /// [`Polygon::contains_segment`] rather than tile by tile, so the time
+    /// doesn't grow with the rectangle's size.
+    pub fn largest_valid_rectangle_general(lines: &[String]) -> Result<Rectangle> {
+        let loops = parse_valid_loops(lines, false)?;
+
+        let [tiles] = &loops[..] else {
+            bail!("General polygons don't support holes");
+        };
+
+        let world = Polygon::new(tiles.iter().map(|t| t.point).collect());
+
+        let found = largest_rectangles_where(tiles, 1, |square| {
+            square
+                .sides()
+                .iter()
+                .all(|&(a, b)| world.contains_segment(a, b))
+        });
+
+        Ok(found[0])
+    }
+}
+
+impl Day9 {
+    /// Reports the area and lattice point counts of the tile floor.
+    ///
+    /// Boundary tiles are the red and green tiles on the loops themselves;
+    /// interior tiles are the green tiles strictly inside the outer loop and
+    /// outside every hole.
+    pub fn atlas_stats(lines: &[String]) -> Result<String> {
+        let loops = parse_valid_loops(lines, false)?;
+
+        let polygons: Vec<Polygon> = loops
+            .iter()
+            .map(|l| Polygon::new(l.iter().map(|t| t.point).collect()))
             .collect();
+        let (outer, holes) = polygons.split_first().unwrap();
 
-        let world = Atlas::new(points.clone());
+        let doubled = outer.doubled_area() - holes.iter().map(|h| h.doubled_area()).sum::<u128>();
+        let area = if doubled.is_multiple_of(2) {
+            (doubled / 2).to_string()
+        } else {
+            format!("{}.5", doubled / 2)
+        };
 
-        let mut max_area = 0;
-        for i in 0..points.len() - 1 {
-            let point = points[i];
-            let points = &points[i + 1..];
+        let boundary: u128 = polygons.iter().map(|p| p.boundary_points()).sum();
+        let interior = outer.interior_points()
+            - holes
+                .iter()
+                .map(|h| h.interior_points() + h.boundary_points())
+                .sum::<u128>();
+
+        Ok(format!(
+            "vertices: {}\nholes: {}\narea: {}\nboundary tiles: {}\ninterior tiles: {}\ntotal tiles: {}",
+            loops.iter().map(|l| l.len()).sum::<usize>(),
+            holes.len(),
+            area,
+            boundary,
+            interior,
+            boundary + interior
+        ))
+    }
 
-            let new_area = points
-                .par_iter()
-                .map(|other| (other, point.area(*other)))
-                .filter(|(_, a)| max_area < *a)
-                .filter(|(other, _)| {
-                    let square = Square::new(&point, other);
+    /// Renders the tile loops and their best valid rectangles as an SVG document.
+    ///
+    /// With `top` greater than one, the runner-up rectangles are drawn behind
+    /// the winner in fading colours.
+    pub fn render_svg(lines: &[String], top: usize, size: u32) -> Result<String> {
+        let loops = parse_valid_loops(lines, true)?;
 
-                    let mut perim = square.perimeter();
+        let world = Atlas::from_loops(&loops);
+        let rects = world.largest_rectangles(&loops.concat(), top.max(1));
 
-                    perim.all(|p| world.is_valid(p))
-                })
-                .map(|(_, a)| a)
-                .max();
+        let loops: Vec<Vec<Point>> = loops
+            .iter()
+            .map(|l| l.iter().map(|t| t.point).collect())
+            .collect();
 
-            if let Some(new_area) = new_area {
-                max_area = new_area;
+        Ok(svg::render(&loops, &rects, size))
+    }
+}
+
+/// A set of tiles that a rectangle's perimeter must stay within.
+pub trait Region: Sync {
+    /// Checks if a tile is part of the region.
+    fn is_valid(&self, point: Point) -> bool;
+}
+
+/// Finds the `k` largest rectangles between two tiles that pass `is_valid`.
+///
+/// Every pair of tiles is ranked by area up front, then candidates are
+/// checked in parallel batches from the largest down, stopping as soon as
+/// `k` valid rectangles are found.
+///
+/// # Returns
+/// Up to `k` rectangles in descending order of area. Rectangles of equal
+/// area keep the input order of their corners.
+fn largest_rectangles_where<F>(tiles: &[RedTile], k: usize, is_valid: F) -> Vec<Rectangle>
+where
+    F: Fn(&Square) -> bool + Sync,
+{
+    const BATCH: usize = 1024;
+
+    let mut candidates: Vec<Rectangle> = tiles
+        .par_iter()
+        .enumerate()
+        .flat_map_iter(|(i, start)| {
+            let ends = &tiles[i + 1..];
+
+            ends.iter().map(move |e| Rectangle::new(*start, *e))
+        })
+        .collect();
+
+    candidates.par_sort_by_key(|r| std::cmp::Reverse(r.area));
+
+    let mut found = vec![];
+    for batch in candidates.chunks(BATCH) {
+        if found.len() >= k {
+            break;
+        }
+
+        let valid: Vec<Rectangle> = batch
+            .par_iter()
+            .filter(|r| is_valid(&r.square()))
+            .copied()
+            .collect();
+
+        found.extend(valid);
+    }
+
+    found.truncate(k);
+
+    found
+}
+
+/// Formats defects one per line.
+fn defect_report(defects: &[Defect]) -> String {
+    defects
+        .iter()
+        .map(|d| d.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parses the red tile loops, remembering which input line each tile came from.
+///
+/// Loops are separated by blank lines. The first loop is the outer boundary
+/// and any further loops are holes cut out of it.
+fn parse_loops(lines: &[String]) -> Vec<Vec<RedTile>> {
+    let mut loops = vec![];
+    let mut current = vec![];
+
+    for (i, s) in lines.iter().enumerate() {
+        if s.trim().is_empty() {
+            if !current.is_empty() {
+                loops.push(current);
+                current = vec![];
             }
-            println!("heck yeah: {} -> {}/{}", max_area, i, points.len());
+            continue;
         }
 
-        max_area.to_string()
+        let mut nums = s.split(',').map(|n| n.parse::<i64>().unwrap());
+        current.push(RedTile {
+            point: Point {
+                x: nums.next().unwrap(),
+                y: nums.next().unwrap(),
+            },
+            line: i + 1,
+        });
     }
+
+    if !current.is_empty() {
+        loops.push(current);
+    }
+
+    loops
+}
+
+/// Parses the red tile loops and [validates](validate_loops) them.
+///
+/// # Returns
+/// The loops, or an error listing every defect found.
+fn parse_valid_loops(lines: &[String], rectilinear: bool) -> Result<Vec<Vec<RedTile>>> {
+    let loops = parse_loops(lines);
+
+    if loops.is_empty() {
+        bail!("No red tiles in input");
+    }
+
+    let defects = validate_loops(&loops, rectilinear);
+    if !defects.is_empty() {
+        bail!("Invalid tile loop:\n{}", defect_report(&defects));
+    }
+
+    Ok(loops)
 }
 
 /// A 2D point with integer coordinates.
 #[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Copy, Clone)]
-struct Point {
-    x: i64,
-    y: i64,
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
 }
 
 impl Point {
@@ -105,6 +395,61 @@ impl Point {
     }
 }
 
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+/// A red tile from the puzzle input.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct RedTile {
+    /// Position of the tile
+    pub point: Point,
+    /// 1-based input line the tile was read from
+    pub line: usize,
+}
+
+impl fmt::Display for RedTile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (line {})", self.point, self.line)
+    }
+}
+
+/// A rectangle spanned by two red tiles as opposite corners.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Rectangle {
+    /// The two opposite corners, in input order
+    pub corners: [RedTile; 2],
+    /// Area in tiles, including the boundary
+    pub area: u64,
+}
+
+impl Rectangle {
+    /// Creates the rectangle spanned by two red tiles.
+    fn new(t1: RedTile, t2: RedTile) -> Self {
+        Self {
+            corners: [t1, t2],
+            area: t1.point.area(t2.point),
+        }
+    }
+
+    /// Returns the axis-aligned [`Square`] covered by this rectangle.
+    fn square(&self) -> Square {
+        Square::new(&self.corners[0].point, &self.corners[1].point)
+    }
+}
+
+impl fmt::Display for Rectangle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} from {} to {}",
+            self.area, self.corners[0], self.corners[1]
+        )
+    }
+}
+
 /// A line segment between two points.
 ///
 /// Points are ordered so p1 < p2 for consistent comparison.
@@ -181,7 +526,18 @@ impl Square {
         }
     }
 
+    /// Returns the rectangle's four sides as pairs of corners.
+    fn sides(&self) -> [(Point, Point); 4] {
+        [
+            (self.nw, self.ne),
+            (self.ne, self.se),
+            (self.se, self.sw),
+            (self.sw, self.nw),
+        ]
+    }
+
     /// Returns an iterator over all points on the rectangle's perimeter.
+    #[cfg(test)]
     fn perimeter(&self) -> impl Iterator<Item = Point> {
         let Point { x: x1, y: y1 } = self.nw;
         let Point { x: x2, y: y2 } = self.se;
@@ -195,6 +551,31 @@ impl Square {
     }
 }
 
+impl Region for Atlas {
+    /// Checks if a point is valid (on boundary or inside polygon, and not
+    /// strictly inside a hole).
+    fn is_valid(&self, point: Point) -> bool {
+        if self.in_hole(point) {
+            return false;
+        }
+
+        if self.is_red(point) {
+            return true;
+        }
+
+        self.is_green(point)
+    }
+}
+
+/// The tiles of an [`Atlas`] that aren't strictly inside one of its holes.
+struct OutsideHoles<'a>(&'a Atlas);
+
+impl Region for OutsideHoles<'_> {
+    fn is_valid(&self, point: Point) -> bool {
+        !self.0.in_hole(point)
+    }
+}
+
 /// A spatial index for efficiently checking point validity within a polygon.
 ///
 /// Stores the polygon boundary as separate horizontal and vertical line segments
@@ -206,6 +587,8 @@ struct Atlas {
     x_lines: Vec<Line>,
     /// Horizontal line segments (sorted by y)
     y_lines: Vec<Line>,
+    /// Holes cut out of the polygon; their boundaries remain valid
+    holes: Vec<Atlas>,
 }
 
 impl Atlas {
@@ -215,7 +598,7 @@ impl Atlas {
     fn new(points: Vec<Point>) -> Self {
         let mut lines = vec![];
 
-        for ps in (&points[..]).windows(2) {
+        for ps in points.windows(2) {
             let p1 = ps[0];
             let p2 = ps[1];
 
@@ -244,56 +627,69 @@ impl Atlas {
             points,
             x_lines,
             y_lines,
+            holes: vec![],
         }
     }
 
-    /// Finds the maximum rectangle area with all perimeter points valid.
-    #[allow(dead_code)]
-    fn max_area(self) -> u64 {
-        (&self.points[..self.points.len() - 1])
-            .par_iter()
-            .enumerate()
-            .flat_map_iter(|(i, start)| {
-                let ends = &self.points[i + 1..];
+    /// Creates an Atlas from an outer loop followed by hole loops.
+    fn from_loops(loops: &[Vec<RedTile>]) -> Self {
+        let points = |l: &Vec<RedTile>| l.iter().map(|t| t.point).collect();
 
-                ends.into_iter().map(move |e| (start, e))
-            })
-            .map(|(start, end)| (start, end, start.area(*end)))
-            .filter(|(s, e, _)| {
-                let square = Square::new(&s, e);
+        let (outer, holes) = loops.split_first().unwrap();
 
-                let mut perim = square.perimeter();
-
-                perim.all(|p| self.is_valid(p))
-            })
-            .map(|(_, _, a)| a)
-            .max()
-            .unwrap()
+        Self {
+            holes: holes.iter().map(|h| Atlas::new(points(h))).collect(),
+            ..Atlas::new(points(outer))
+        }
     }
 
-    /// Checks if a point is valid (on boundary or inside polygon).
-    fn is_valid(&self, point: Point) -> bool {
-        if self.is_red(point) {
-            return true;
-        }
+    /// Finds the `k` largest rectangles between tiles with all perimeter points valid.
+    ///
+    /// `tiles` must include every vertex of the Atlas and its holes.
+    ///
+    /// Perimeters are checked against a [`Coverage`] grid, so each check
+    /// takes constant time. When there are holes, a second grid of just the
+    /// hole tiles rejects rectangles that enclose part of a hole, which the
+    /// perimeter alone can't see.
+    ///
+    /// # Returns
+    /// Up to `k` rectangles in descending order of area, as for
+    /// [`largest_rectangles_where`].
+    fn largest_rectangles(&self, tiles: &[RedTile], k: usize) -> Vec<Rectangle> {
+        let points: Vec<Point> = tiles.iter().map(|t| t.point).collect();
+        let coverage = Coverage::new(&points, self);
+        let hole_free =
+            (!self.holes.is_empty()).then(|| Coverage::new(&points, &OutsideHoles(self)));
+
+        largest_rectangles_where(tiles, k, |square| {
+            coverage.contains_perimeter(square)
+                && hole_free.as_ref().is_none_or(|h| h.contains(square))
+        })
+    }
 
-        self.is_green(point)
+    /// Checks if a point is strictly inside one of the holes.
+    fn in_hole(&self, point: Point) -> bool {
+        self.holes
+            .iter()
+            .any(|h| !h.on_boundary(point) && h.is_valid(point))
     }
 
     /// Checks if a point is exactly on a polygon vertex.
     fn is_red(&self, point: Point) -> bool {
-        self.points.iter().any(|p| *p == point)
+        self.points.contains(&point)
     }
 
-    /// Checks if a point is on a polygon edge or inside the polygon.
-    fn is_green(&self, point: Point) -> bool {
-        let on_bound = self
-            .x_lines
+    /// Checks if a point is on one of the polygon's edges.
+    fn on_boundary(&self, point: Point) -> bool {
+        self.x_lines
             .iter()
             .chain(self.y_lines.iter())
-            .any(|l| l.x_in(point.x) && l.y_in(point.y));
+            .any(|l| l.x_in(point.x) && l.y_in(point.y))
+    }
 
-        if on_bound {
+    /// Checks if a point is on a polygon edge or inside the polygon.
+    fn is_green(&self, point: Point) -> bool {
+        if self.on_boundary(point) {
             return true;
         }
 
@@ -494,4 +890,298 @@ mod tests {
         let result = Day9::part_a(&input);
         assert_eq!(result, "3");
     }
+
+    fn small_input() -> Vec<String> {
+        ["7,1", "11,1", "11,7", "9,7", "9,5", "2,5", "2,3", "7,3"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_largest_rectangle_corners() {
+        let rect = Day9::largest_rectangle(&small_input());
+        assert_eq!(rect.area, 50);
+        assert_eq!(rect.corners[0].point, Point { x: 11, y: 7 });
+        assert_eq!(rect.corners[0].line, 3);
+        assert_eq!(rect.corners[1].point, Point { x: 2, y: 3 });
+        assert_eq!(rect.corners[1].line, 7);
+    }
+
+    #[test]
+    fn test_largest_valid_rectangle_corners() {
+        let rect = Day9::largest_valid_rectangle(&small_input()).unwrap();
+        assert_eq!(rect.area, 24);
+        assert_eq!(rect.corners[0].line, 5);
+        assert_eq!(rect.corners[1].line, 7);
+    }
+
+    #[test]
+    fn test_mode_explain() {
+        let out = Day9::mode(&small_input(), "explain", &Params::default()).unwrap();
+        assert_eq!(
+            out,
+            "a: 50 from 11,7 (line 3) to 2,3 (line 7)\nb: 24 from 9,5 (line 5) to 2,3 (line 7)"
+        );
+        assert!(Day9::mode(&small_input(), "nope", &Params::default()).is_err());
+    }
+
+    #[test]
+    fn test_general_matches_atlas() {
+        let rect = Day9::largest_valid_rectangle_general(&small_input()).unwrap();
+        assert_eq!(rect.area, 24);
+    }
+
+    #[test]
+    fn test_general_diagonal_polygon() {
+        // A right triangle: only rectangles hugging the legs fit inside
+        let input: Vec<String> = ["0,0", "6,0", "3,3", "0,6"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let rect = Day9::largest_valid_rectangle_general(&input).unwrap();
+        assert_eq!(rect.area, 16);
+        assert_eq!(
+            Day9::mode(&input, "general", &Params::default()).unwrap(),
+            "16"
+        );
+    }
+
+    #[test]
+    fn test_general_large_span() {
+        // The triangle above scaled up a million times; the winning square
+        // has sides of 3,000,001 tiles
+        let input: Vec<String> = ["0,0", "6000000,0", "3000000,3000000", "0,6000000"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let rect = Day9::largest_valid_rectangle_general(&input).unwrap();
+        assert_eq!(rect.area, 3_000_001 * 3_000_001);
+    }
+
+    #[test]
+    fn test_general_matches_tile_by_tile() {
+        for input in [small_input(), pocket_input(), notch_input()] {
+            let points: Vec<Point> = parse_loops(&input)[0].iter().map(|t| t.point).collect();
+            let world = Polygon::new(points.clone());
+
+            let expected = points
+                .iter()
+                .enumerate()
+                .flat_map(|(i, p)| points[i..].iter().map(move |q| Square::new(p, q)))
+                .filter(|s| s.perimeter().all(|t| world.contains(t)))
+                .map(|s| (s.se.x - s.nw.x + 1) as u64 * (s.se.y - s.nw.y + 1) as u64)
+                .max()
+                .unwrap();
+
+            let rect = Day9::largest_valid_rectangle_general(&input).unwrap();
+            assert_eq!(rect.area, expected);
+        }
+    }
+
+    fn notch_input() -> Vec<String> {
+        // A square with a V-shaped notch cut into its top edge
+        ["0,0", "8,0", "8,8", "4,2", "0,8"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    fn pocket_input() -> Vec<String> {
+        // A one-tile-wide corridor from the top edge opens into a pocket of
+        // outside tiles, which a perimeter check can straddle
+        [
+            "0,0", "4,0", "4,6", "2,6", "2,8", "8,8", "8,6", "5,6", "5,0", "10,0", "10,10", "0,10",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn test_contained_matches_perimeter_without_pockets() {
+        let rect = Day9::largest_contained_rectangle(&small_input()).unwrap();
+        assert_eq!(rect.area, 24);
+    }
+
+    #[test]
+    fn test_contained_differs_from_perimeter_with_pocket() {
+        let perimeter = Day9::largest_valid_rectangle(&pocket_input()).unwrap();
+        let contained = Day9::largest_contained_rectangle(&pocket_input()).unwrap();
+        assert_eq!(perimeter.area, 121);
+        assert_eq!(contained.area, 63);
+        assert_eq!(
+            Day9::mode(&pocket_input(), "contained", &Params::default()).unwrap(),
+            "63"
+        );
+    }
+
+    #[test]
+    fn test_contained_u_shape() {
+        // A U shape has no hidden pockets, so both checks agree
+        let input: Vec<String> = ["0,0", "2,0", "2,6", "6,6", "6,0", "8,0", "8,8", "0,8"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let perimeter = Day9::largest_valid_rectangle(&input).unwrap();
+        let contained = Day9::largest_contained_rectangle(&input).unwrap();
+        assert_eq!(perimeter.area, contained.area);
+        assert_eq!(contained.area, 27);
+    }
+
+    fn pillar_input() -> Vec<String> {
+        // A 9x9 room with a 3x3 pillar whose interior tile is (4,4)
+        ["0,0", "8,0", "8,8", "0,8", "", "3,3", "5,3", "5,5", "3,5"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_loops_tracks_lines() {
+        let loops = parse_loops(&pillar_input());
+        assert_eq!(loops.len(), 2);
+        assert_eq!(loops[0].len(), 4);
+        assert_eq!(loops[1][0].line, 6);
+        assert_eq!(loops[1][0].point, Point { x: 3, y: 3 });
+    }
+
+    #[test]
+    fn test_atlas_with_hole() {
+        let world = Atlas::from_loops(&parse_loops(&pillar_input()));
+        assert!(world.is_valid(Point { x: 1, y: 1 }));
+        assert!(world.is_valid(Point { x: 3, y: 4 }));
+        assert!(!world.is_valid(Point { x: 4, y: 4 }));
+        assert!(!world.is_valid(Point { x: 9, y: 4 }));
+    }
+
+    #[test]
+    fn test_rectangles_respect_hole() {
+        // The whole room's perimeter avoids the pillar, but it encloses the
+        // pillar, so rectangles must stop at the pillar's far edge
+        let perimeter = Day9::largest_valid_rectangle(&pillar_input()).unwrap();
+        assert_eq!(perimeter.area, 24);
+
+        let top = Day9::largest_valid_rectangles(&pillar_input(), 100).unwrap();
+        assert!(top.iter().all(|r| {
+            let Square { nw, se, .. } = r.square();
+            !(nw.x < 4 && 4 < se.x && nw.y < 4 && 4 < se.y)
+        }));
+
+        let contained = Day9::largest_contained_rectangle(&pillar_input()).unwrap();
+        assert_eq!(contained.area, 24);
+    }
+
+    #[test]
+    fn test_atlas_stats_with_hole() {
+        let out = Day9::mode(&pillar_input(), "atlas-stats", &Params::default()).unwrap();
+        assert_eq!(
+            out,
+            "vertices: 8\nholes: 1\narea: 60\nboundary tiles: 40\ninterior tiles: 40\ntotal tiles: 80"
+        );
+    }
+
+    #[test]
+    fn test_general_rejects_holes() {
+        assert!(Day9::largest_valid_rectangle_general(&pillar_input()).is_err());
+    }
+
+    #[test]
+    fn test_atlas_stats() {
+        let out = Day9::mode(&small_input(), "atlas-stats", &Params::default()).unwrap();
+        assert_eq!(
+            out,
+            "vertices: 8\nholes: 0\narea: 30\nboundary tiles: 30\ninterior tiles: 16\ntotal tiles: 46"
+        );
+    }
+
+    #[test]
+    fn test_atlas_stats_rejects_degenerate_loops() {
+        for input in [vec!["0,0", "5,0"], vec!["0,0", "5,0", "9,0"]] {
+            let input: Vec<String> = input.iter().map(|s| s.to_string()).collect();
+            let err = Day9::atlas_stats(&input).unwrap_err();
+            assert!(
+                err.to_string()
+                    .contains("loop starting on line 1 encloses no area")
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_svg_top_candidates() {
+        let svg = Day9::render_svg(&small_input(), 3, 200).unwrap();
+        assert_eq!(svg.matches("<circle").count(), 8);
+        assert_eq!(svg.matches(r#"stroke="blue""#).count(), 1);
+        assert_eq!(svg.matches(r#"stroke="orange""#).count(), 2);
+    }
+
+    #[test]
+    fn test_largest_rectangles_sorted() {
+        let tiles = parse_loops(&small_input()).concat();
+        let world = Atlas::new(tiles.iter().map(|t| t.point).collect());
+        let rects = world.largest_rectangles(&tiles, 5);
+        let areas: Vec<u64> = rects.iter().map(|r| r.area).collect();
+        assert_eq!(areas.len(), 5);
+        assert_eq!(areas[0], 24);
+        assert!(areas.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn test_largest_rectangles_matches_brute_force() {
+        let tiles = parse_loops(&pocket_input()).concat();
+        let world = Atlas::new(tiles.iter().map(|t| t.point).collect());
+
+        let mut expected: Vec<u64> = tiles
+            .iter()
+            .enumerate()
+            .flat_map(|(i, t)| tiles[i + 1..].iter().map(|o| Rectangle::new(*t, *o)))
+            .filter(|r| r.square().perimeter().all(|p| world.is_valid(p)))
+            .map(|r| r.area)
+            .collect();
+        expected.sort_by(|a, b| b.cmp(a));
+        expected.truncate(8);
+
+        let areas: Vec<u64> = world
+            .largest_rectangles(&tiles, 8)
+            .iter()
+            .map(|r| r.area)
+            .collect();
+        assert_eq!(areas, expected);
+    }
+
+    #[test]
+    fn test_largest_rectangles_more_than_exist() {
+        let tiles = parse_loops(&small_input()).concat();
+        let world = Atlas::new(tiles.iter().map(|t| t.point).collect());
+        let all = world.largest_rectangles(&tiles, 1000);
+        assert!(all.len() < 28);
+        assert_eq!(world.largest_rectangles(&tiles, 0).len(), 0);
+    }
+
+    #[test]
+    fn test_mode_top() {
+        let params = Params::from_strs(&["k=2"]);
+        let out = Day9::mode(&small_input(), "top", &params).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("1: 24 from "));
+        assert!(lines[1].starts_with("2: "));
+    }
+
+    #[test]
+    fn test_largest_valid_rectangle_rejects_diagonal() {
+        let input: Vec<String> = ["0,0", "4,0", "4,4", "1,3", "0,3"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let err = Day9::largest_valid_rectangle(&input).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("diagonal edge between lines 3 and 4")
+        );
+        assert_eq!(
+            Day9::mode(&input, "validate", &Params::default()).unwrap(),
+            "diagonal edge between lines 3 and 4"
+        );
+    }
 }
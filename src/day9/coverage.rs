@@ -0,0 +1,143 @@
+//! Whole-rectangle containment checks over a rectilinear region.
+//!
+//! Checking only a rectangle's perimeter misses pockets of outside tiles that
+//! the perimeter straddles. [`Coverage`] instead compresses the plane into
+//! cells that are uniformly valid or invalid, then keeps a 2D prefix sum of
+//! invalid cells so any rectangle can be checked in constant time.
+
+use rayon::prelude::*;
+
+use super::{Point, Region, Square};
+
+/// A compressed grid of valid and invalid cells with prefix sums.
+pub struct Coverage {
+    /// Sorted cell boundaries along x; cell `i` spans `xs[i]..xs[i + 1]`
+    xs: Vec<i64>,
+    /// Sorted cell boundaries along y; cell `j` spans `ys[j]..ys[j + 1]`
+    ys: Vec<i64>,
+    /// `invalid[j][i]` counts invalid cells in rows `..j` and columns `..i`
+    invalid: Vec<Vec<u64>>,
+}
+
+impl Coverage {
+    /// Builds the coverage grid for a region bounded by axis-aligned edges.
+    ///
+    /// Cell boundaries are placed at every vertex coordinate and one past it,
+    /// so each edge gets its own row or column of cells. With only
+    /// axis-aligned edges every cell is then entirely inside or entirely
+    /// outside the region, and one sample point decides it.
+    ///
+    /// # Arguments
+    /// * `vertices` - Every vertex of the region's boundary
+    /// * `region` - The region to sample cells against
+    pub fn new<R: Region>(vertices: &[Point], region: &R) -> Self {
+        let xs = boundaries(vertices.iter().map(|p| p.x));
+        let ys = boundaries(vertices.iter().map(|p| p.y));
+
+        let cells: Vec<Vec<bool>> = ys
+            .par_iter()
+            .map(|&y| {
+                xs.iter()
+                    .map(|&x| !region.is_valid(Point { x, y }))
+                    .collect()
+            })
+            .collect();
+
+        let mut invalid = vec![vec![0; xs.len() + 1]; ys.len() + 1];
+        for j in 0..ys.len() {
+            for i in 0..xs.len() {
+                invalid[j + 1][i + 1] =
+                    invalid[j][i + 1] + invalid[j + 1][i] - invalid[j][i] + cells[j][i] as u64;
+            }
+        }
+
+        Self { xs, ys, invalid }
+    }
+
+    /// Checks if every tile of a rectangle is inside the region.
+    ///
+    /// The rectangle's corners must lie on vertex coordinates of the region,
+    /// which holds for any rectangle spanned by two red tiles.
+    pub fn contains(&self, square: &Square) -> bool {
+        let i1 = self.xs.binary_search(&square.nw.x).unwrap();
+        let i2 = self.xs.binary_search(&square.se.x).unwrap() + 1;
+        let j1 = self.ys.binary_search(&square.nw.y).unwrap();
+        let j2 = self.ys.binary_search(&square.se.y).unwrap() + 1;
+
+        let inv = &self.invalid;
+        let count = inv[j2][i2] + inv[j1][i1] - inv[j1][i2] - inv[j2][i1];
+
+        count == 0
+    }
+
+    /// Checks if every tile on a rectangle's perimeter is inside the region.
+    ///
+    /// Each side is a one-tile-wide rectangle, so this is four [`contains`]
+    /// checks, with the same requirement on the corners.
+    ///
+    /// [`contains`]: Coverage::contains
+    pub fn contains_perimeter(&self, square: &Square) -> bool {
+        let Square { nw, ne, sw, se } = square;
+
+        self.contains(&Square::new(nw, ne))
+            && self.contains(&Square::new(ne, se))
+            && self.contains(&Square::new(sw, se))
+            && self.contains(&Square::new(nw, sw))
+    }
+}
+
+/// Collects the sorted, deduplicated cell boundaries for one axis.
+fn boundaries(coords: impl Iterator<Item = i64>) -> Vec<i64> {
+    let mut bounds: Vec<i64> = coords.flat_map(|c| [c, c + 1]).collect();
+
+    bounds.sort();
+    bounds.dedup();
+
+    bounds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day9::Polygon;
+
+    fn p(x: i64, y: i64) -> Point {
+        Point { x, y }
+    }
+
+    #[test]
+    fn test_boundaries() {
+        let bounds = boundaries([5, 1, 2].into_iter());
+        assert_eq!(bounds, vec![1, 2, 3, 5, 6]);
+    }
+
+    #[test]
+    fn test_contains_pocket() {
+        // A square with a one-tile-wide corridor leading into a hidden pocket
+        let vertices = vec![
+            p(0, 0),
+            p(4, 0),
+            p(4, 6),
+            p(2, 6),
+            p(2, 8),
+            p(8, 8),
+            p(8, 6),
+            p(5, 6),
+            p(5, 0),
+            p(10, 0),
+            p(10, 10),
+            p(0, 10),
+        ];
+        let polygon = Polygon::new(vertices.clone());
+        let coverage = Coverage::new(&vertices, &polygon);
+
+        assert!(!coverage.contains(&Square::new(&p(0, 0), &p(10, 10))));
+        assert!(coverage.contains(&Square::new(&p(0, 0), &p(8, 6))));
+        assert!(coverage.contains(&Square::new(&p(2, 8), &p(10, 10))));
+        assert!(!coverage.contains(&Square::new(&p(2, 8), &p(10, 0))));
+
+        // The pocket hides inside the full square, not on its perimeter
+        assert!(coverage.contains_perimeter(&Square::new(&p(0, 0), &p(10, 10))));
+        assert!(!coverage.contains_perimeter(&Square::new(&p(4, 6), &p(10, 10))));
+    }
+}
@@ -0,0 +1,464 @@
+//! General lattice polygons with exact integer predicates.
+//!
+//! Unlike [`Atlas`](super::Atlas), which only understands axis-aligned edges,
+//! a [`Polygon`] accepts any closed loop of lattice points. Point containment
+//! uses the winding number, and every geometric decision goes through
+//! [`orientation`], which is exact for any `i64` coordinates.
+//!
+//! Areas are another matter: [`cross`] and [`Polygon::doubled_area`] are
+//! only exact for coordinates within [`MAX_COORDINATE`] of the origin, which
+//! [validation](super::validate_loops) enforces before any area is taken.
+
+use std::cmp::Ordering;
+
+use super::{Point, Region};
+
+/// The largest coordinate magnitude whose areas are computed exactly.
+///
+/// Within this bound each [`cross`] term stays below 2^62, so even a sum of
+/// one per addressable vertex can't overflow `i128`, and a rectangle's area
+/// fits in `i64`.
+pub const MAX_COORDINATE: i64 = 1 << 30;
+
+/// The turn direction of three points.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Orientation {
+    /// `a -> b -> c` turns left
+    CounterClockwise,
+    /// `a -> b -> c` turns right
+    Clockwise,
+    /// All three points lie on one line
+    Collinear,
+}
+
+/// Cross product of `o -> a` and `o -> b`.
+///
+/// Positive for a counter-clockwise turn, negative for clockwise and zero
+/// when the three points are collinear. Exact as long as every coordinate
+/// is within [`MAX_COORDINATE`]; beyond that the products can overflow
+/// `i128`, so use [`orientation`] to classify turns of arbitrary points.
+pub fn cross(o: Point, a: Point, b: Point) -> i128 {
+    let (ax, ay) = (a.x as i128 - o.x as i128, a.y as i128 - o.y as i128);
+    let (bx, by) = (b.x as i128 - o.x as i128, b.y as i128 - o.y as i128);
+
+    ax * by - ay * bx
+}
+
+/// Multiplies two factors below 2^64 in size, returning the product's sign
+/// and its magnitude, which can reach 2^128 and so only fits unsigned.
+fn signed_product(a: i128, b: i128) -> (i128, u128) {
+    (a.signum() * b.signum(), a.unsigned_abs() * b.unsigned_abs())
+}
+
+/// Classifies the turn made by `a -> b -> c`.
+///
+/// This is the sign of [`cross`], found without overflow for any `i64`
+/// coordinates by comparing its two products as sign and magnitude rather
+/// than subtracting them.
+pub fn orientation(a: Point, b: Point, c: Point) -> Orientation {
+    let (bx, by) = (b.x as i128 - a.x as i128, b.y as i128 - a.y as i128);
+    let (cx, cy) = (c.x as i128 - a.x as i128, c.y as i128 - a.y as i128);
+
+    let (left_sign, left) = signed_product(bx, cy);
+    let (right_sign, right) = signed_product(by, cx);
+
+    let ordering = match left_sign.cmp(&right_sign) {
+        Ordering::Equal if left_sign > 0 => left.cmp(&right),
+        Ordering::Equal if left_sign < 0 => right.cmp(&left),
+        ordering => ordering,
+    };
+
+    match ordering {
+        Ordering::Greater => Orientation::CounterClockwise,
+        Ordering::Less => Orientation::Clockwise,
+        Ordering::Equal => Orientation::Collinear,
+    }
+}
+
+/// Checks whether `p` lies on the closed segment `a`-`b`.
+pub fn on_segment(a: Point, b: Point, p: Point) -> bool {
+    orientation(a, b, p) == Orientation::Collinear && in_box(a, b, p)
+}
+
+/// Checks whether `p` lies within the bounding box of segment `a`-`b`.
+fn in_box(a: Point, b: Point, p: Point) -> bool {
+    a.x.min(b.x) <= p.x && p.x <= a.x.max(b.x) && a.y.min(b.y) <= p.y && p.y <= a.y.max(b.y)
+}
+
+/// Checks whether the closed segments `a1`-`a2` and `b1`-`b2` share any point.
+pub fn segments_intersect(a1: Point, a2: Point, b1: Point, b2: Point) -> bool {
+    let o1 = orientation(b1, b2, a1);
+    let o2 = orientation(b1, b2, a2);
+    let o3 = orientation(a1, a2, b1);
+    let o4 = orientation(a1, a2, b2);
+
+    let proper = o1 != o2 && o3 != o4 && ![o1, o2, o3, o4].contains(&Orientation::Collinear);
+
+    proper
+        || on_segment(b1, b2, a1)
+        || on_segment(b1, b2, a2)
+        || on_segment(a1, a2, b1)
+        || on_segment(a1, a2, b2)
+}
+
+/// A closed polygon over lattice points, with no restriction on edge direction.
+///
+/// The last vertex is implicitly connected back to the first.
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    /// Vertices in loop order
+    vertices: Vec<Point>,
+}
+
+impl Polygon {
+    /// Creates a polygon from its vertices in loop order.
+    pub fn new(vertices: Vec<Point>) -> Self {
+        Self { vertices }
+    }
+
+    /// Returns an iterator over the polygon's edges, including the closing edge.
+    pub fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        let n = self.vertices.len();
+
+        (0..n).map(move |i| (self.vertices[i], self.vertices[(i + 1) % n]))
+    }
+
+    /// Checks if a point lies on one of the polygon's edges.
+    pub fn on_boundary(&self, point: Point) -> bool {
+        self.edges().any(|(a, b)| on_segment(a, b, point))
+    }
+
+    /// Computes how many times the polygon winds around a point.
+    ///
+    /// Non-zero means the point is inside. The result is only meaningful for
+    /// points that aren't on the boundary.
+    pub fn winding_number(&self, point: Point) -> i64 {
+        let mut winding = 0;
+
+        for (a, b) in self.edges() {
+            if a.y <= point.y {
+                if b.y > point.y && orientation(a, b, point) == Orientation::CounterClockwise {
+                    winding += 1;
+                }
+            } else if b.y <= point.y && orientation(a, b, point) == Orientation::Clockwise {
+                winding -= 1;
+            }
+        }
+
+        winding
+    }
+
+    /// Checks if a point is on the boundary or inside the polygon.
+    pub fn contains(&self, point: Point) -> bool {
+        self.on_boundary(point) || self.winding_number(point) != 0
+    }
+
+    /// Checks if every lattice point on the axis-aligned segment `a`-`b` is
+    /// on the boundary or inside the polygon.
+    ///
+    /// Rather than testing each point, this finds everywhere the boundary
+    /// meets the segment: crossings, touching vertices and overlapping edges.
+    /// Between two neighbouring meeting points the segment doesn't cross the
+    /// boundary, so it's either inside or outside throughout, and one lattice
+    /// point decides it. Stretches with no lattice point can't hold a tile
+    /// and are skipped.
+    ///
+    /// Like the areas, the meeting points are only exact for coordinates
+    /// within [`MAX_COORDINATE`].
+    pub fn contains_segment(&self, a: Point, b: Point) -> bool {
+        // Work along x, transposing vertical segments
+        let horizontal = a.y == b.y;
+        let flip = |p: Point| {
+            let (x, y) = if horizontal { (p.x, p.y) } else { (p.y, p.x) };
+            (x as i128, y as i128)
+        };
+
+        let ((ax, y), (bx, _)) = (flip(a), flip(b));
+        let (lo, hi) = (ax.min(bx), ax.max(bx));
+
+        // Where the boundary meets the segment, as the floor and ceiling of
+        // x, bracketed by sentinels just outside each end
+        let mut hits = vec![(lo - 1, lo - 1), (hi + 1, hi + 1)];
+
+        for (p, q) in self.edges() {
+            let ((px, py), (qx, qy)) = (flip(p), flip(q));
+
+            if py == qy {
+                let (start, end) = (px.min(qx).max(lo), px.max(qx).min(hi));
+
+                if py == y && start <= end {
+                    hits.extend([(start, start), (end, end)]);
+                }
+                continue;
+            }
+
+            if (py - y).signum() * (qy - y).signum() > 0 {
+                continue;
+            }
+
+            let (mut num, mut den) = ((y - py) * (qx - px), qy - py);
+            if den < 0 {
+                (num, den) = (-num, -den);
+            }
+
+            let floor = px + num.div_euclid(den);
+            let ceil = floor + (num.rem_euclid(den) != 0) as i128;
+
+            if ceil >= lo && floor <= hi {
+                hits.push((floor, ceil));
+            }
+        }
+
+        hits.sort_unstable();
+
+        hits.windows(2).all(|w| {
+            let (x, last) = (w[0].0 + 1, w[1].1 - 1);
+
+            x > last || {
+                let (x, y) = (x as i64, y as i64);
+                let point = if horizontal {
+                    Point { x, y }
+                } else {
+                    Point { x: y, y: x }
+                };
+
+                self.contains(point)
+            }
+        })
+    }
+
+    /// Computes twice the enclosed area using the shoelace formula.
+    ///
+    /// Doubling keeps the result an exact integer for any lattice polygon
+    /// whose coordinates are within [`MAX_COORDINATE`].
+    pub fn doubled_area(&self) -> u128 {
+        let origin = Point { x: 0, y: 0 };
+
+        self.edges()
+            .map(|(a, b)| cross(origin, a, b))
+            .sum::<i128>()
+            .unsigned_abs()
+    }
+
+    /// Counts the lattice points lying on the polygon's edges.
+    pub fn boundary_points(&self) -> u128 {
+        self.edges()
+            .map(|(a, b)| {
+                let dx = (b.x as i128 - a.x as i128).unsigned_abs();
+                let dy = (b.y as i128 - a.y as i128).unsigned_abs();
+
+                gcd(dx, dy)
+            })
+            .sum()
+    }
+
+    /// Counts the lattice points strictly inside the polygon via Pick's theorem.
+    ///
+    /// Pick's theorem states `A = I + B/2 - 1`, so `I = (2A - B + 2) / 2`.
+    pub fn interior_points(&self) -> u128 {
+        (self.doubled_area() + 2 - self.boundary_points()) / 2
+    }
+}
+
+/// Greatest common divisor by Euclid's algorithm.
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+impl Region for Polygon {
+    fn is_valid(&self, point: Point) -> bool {
+        self.contains(point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(x: i64, y: i64) -> Point {
+        Point { x, y }
+    }
+
+    #[test]
+    fn test_orientation() {
+        assert_eq!(
+            orientation(p(0, 0), p(4, 0), p(4, 4)),
+            Orientation::CounterClockwise
+        );
+        assert_eq!(
+            orientation(p(0, 0), p(4, 0), p(4, -4)),
+            Orientation::Clockwise
+        );
+        assert_eq!(
+            orientation(p(0, 0), p(4, 0), p(9, 0)),
+            Orientation::Collinear
+        );
+    }
+
+    #[test]
+    fn test_orientation_large_coordinates() {
+        // The products here overflow i64 but must still be exact
+        let big = i64::MAX / 2;
+        assert_eq!(
+            orientation(p(-big, -big), p(big, big - 1), p(big, big)),
+            Orientation::CounterClockwise
+        );
+    }
+
+    #[test]
+    fn test_orientation_extreme_coordinates() {
+        // Differences here overflow i64, and the products overflow i128
+        let (min, max) = (i64::MIN, i64::MAX);
+        assert_eq!(
+            orientation(p(min, min), p(max, min), p(max, max)),
+            Orientation::CounterClockwise
+        );
+        assert_eq!(
+            orientation(p(min, min), p(max, max), p(max, min)),
+            Orientation::Clockwise
+        );
+        assert_eq!(
+            orientation(p(min, min), p(0, 0), p(max - 1, max - 1)),
+            Orientation::Collinear
+        );
+        assert_eq!(
+            orientation(p(min, max), p(max, min), p(max, min + 1)),
+            Orientation::CounterClockwise
+        );
+    }
+
+    #[test]
+    fn test_boundary_points_extreme_coordinates() {
+        let (min, max) = (i64::MIN, i64::MAX);
+        let segment = Polygon::new(vec![p(min, 0), p(max, 0)]);
+        // Both edges cover every integer x between the ends
+        assert_eq!(segment.boundary_points(), 2 * u64::MAX as u128);
+    }
+
+    #[test]
+    fn test_segments_intersect() {
+        assert!(segments_intersect(p(0, 0), p(4, 0), p(2, -2), p(2, 2)));
+        assert!(segments_intersect(p(0, 0), p(4, 0), p(4, 0), p(4, 2)));
+        assert!(segments_intersect(p(0, 0), p(4, 0), p(2, 0), p(6, 0)));
+        assert!(segments_intersect(p(0, 0), p(4, 4), p(0, 4), p(4, 0)));
+        assert!(!segments_intersect(p(0, 0), p(4, 0), p(5, 0), p(6, 0)));
+        assert!(!segments_intersect(p(0, 0), p(4, 0), p(0, 1), p(4, 1)));
+    }
+
+    #[test]
+    fn test_triangle_contains() {
+        let tri = Polygon::new(vec![p(0, 0), p(6, 0), p(0, 6)]);
+        assert!(tri.contains(p(1, 1)));
+        assert!(tri.contains(p(3, 3))); // on the hypotenuse
+        assert!(tri.contains(p(0, 0)));
+        assert!(!tri.contains(p(4, 4)));
+        assert!(!tri.contains(p(-1, 0)));
+        assert_eq!(tri.winding_number(p(1, 1)), 1);
+    }
+
+    #[test]
+    fn test_winding_direction() {
+        let ccw = Polygon::new(vec![p(0, 0), p(4, 0), p(4, 4), p(0, 4)]);
+        let cw = Polygon::new(vec![p(0, 0), p(0, 4), p(4, 4), p(4, 0)]);
+        assert_eq!(ccw.winding_number(p(2, 2)), 1);
+        assert_eq!(cw.winding_number(p(2, 2)), -1);
+    }
+
+    #[test]
+    fn test_lattice_counts_square() {
+        let square = Polygon::new(vec![p(0, 0), p(4, 0), p(4, 4), p(0, 4)]);
+        assert_eq!(square.doubled_area(), 32);
+        assert_eq!(square.boundary_points(), 16);
+        assert_eq!(square.interior_points(), 9);
+    }
+
+    #[test]
+    fn test_lattice_counts_triangle() {
+        // Area 4.5 with a diagonal hypotenuse holding 4 lattice points
+        let tri = Polygon::new(vec![p(0, 0), p(3, 0), p(0, 3)]);
+        assert_eq!(tri.doubled_area(), 9);
+        assert_eq!(tri.boundary_points(), 9);
+        assert_eq!(tri.interior_points(), 1);
+    }
+
+    #[test]
+    fn test_lattice_counts_match_brute_force() {
+        let notch = Polygon::new(vec![p(0, 0), p(8, 0), p(8, 8), p(4, 2), p(0, 8)]);
+
+        let mut boundary = 0;
+        let mut interior = 0;
+        for x in -1..=9 {
+            for y in -1..=9 {
+                if notch.on_boundary(p(x, y)) {
+                    boundary += 1;
+                } else if notch.contains(p(x, y)) {
+                    interior += 1;
+                }
+            }
+        }
+
+        assert_eq!(notch.boundary_points(), boundary);
+        assert_eq!(notch.interior_points(), interior);
+    }
+
+    #[test]
+    fn test_contains_segment_matches_brute_force() {
+        let notch = Polygon::new(vec![p(0, 0), p(8, 0), p(8, 8), p(4, 2), p(0, 8)]);
+        // The sliver cut out between x = 4 and 5 never holds a lattice point
+        let sliver = Polygon::new(vec![
+            p(0, 0),
+            p(8, 0),
+            p(8, 8),
+            p(5, 8),
+            p(5, 2),
+            p(4, 8),
+            p(0, 8),
+        ]);
+        let tri = Polygon::new(vec![p(0, 0), p(6, 0), p(3, 3), p(0, 6)]);
+
+        for polygon in [notch, sliver, tri] {
+            for fixed in -1..=9 {
+                for start in -1..=9 {
+                    for end in start..=9 {
+                        let across = (start..=end).all(|x| polygon.contains(p(x, fixed)));
+                        let down = (start..=end).all(|y| polygon.contains(p(fixed, y)));
+
+                        assert_eq!(
+                            polygon.contains_segment(p(start, fixed), p(end, fixed)),
+                            across
+                        );
+                        assert_eq!(
+                            polygon.contains_segment(p(fixed, end), p(fixed, start)),
+                            down
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_contains_segment_sliver() {
+        let sliver = Polygon::new(vec![
+            p(0, 0),
+            p(8, 0),
+            p(8, 8),
+            p(5, 8),
+            p(5, 2),
+            p(4, 8),
+            p(0, 8),
+        ]);
+        assert!(sliver.contains_segment(p(0, 5), p(8, 5)));
+        assert!(!sliver.contains_segment(p(0, 9), p(8, 9)));
+    }
+
+    #[test]
+    fn test_concave_contains() {
+        // A square with a V-shaped notch cut into the top edge
+        let notch = Polygon::new(vec![p(0, 0), p(8, 0), p(8, 8), p(4, 2), p(0, 8)]);
+        assert!(notch.contains(p(4, 1)));
+        assert!(notch.contains(p(1, 6)));
+        assert!(!notch.contains(p(4, 6)));
+        assert!(notch.on_boundary(p(2, 5)));
+    }
+}
@@ -0,0 +1,186 @@
+//! SVG rendering of the tile loops and candidate rectangles.
+//!
+//! Coordinates are scaled so the loop's bounding box fits a square canvas of
+//! the requested size, with the y-axis pointing down as in the puzzle input.
+
+use std::fmt::Write;
+
+use super::{Point, Rectangle};
+
+/// Padding around the drawing, in pixels.
+const MARGIN: f64 = 10.0;
+
+/// Maps puzzle coordinates onto the canvas.
+struct Scale {
+    min_x: i64,
+    min_y: i64,
+    factor: f64,
+}
+
+impl Scale {
+    /// Creates a scale fitting the points' bounding box into `size` pixels.
+    fn fit(points: &[Point], size: u32) -> Self {
+        let min_x = points.iter().map(|p| p.x).min().unwrap_or(0);
+        let max_x = points.iter().map(|p| p.x).max().unwrap_or(0);
+        let min_y = points.iter().map(|p| p.y).min().unwrap_or(0);
+        let max_y = points.iter().map(|p| p.y).max().unwrap_or(0);
+
+        let span = (max_x - min_x).max(max_y - min_y).max(1) as f64;
+        let factor = (size as f64 - 2.0 * MARGIN) / span;
+
+        Self {
+            min_x,
+            min_y,
+            factor,
+        }
+    }
+
+    /// Converts a point to canvas coordinates.
+    fn apply(&self, p: Point) -> (f64, f64) {
+        let x = (p.x - self.min_x) as f64 * self.factor + MARGIN;
+        let y = (p.y - self.min_y) as f64 * self.factor + MARGIN;
+
+        (x, y)
+    }
+}
+
+/// Renders the tile loops with their red vertices and a list of rectangles.
+///
+/// The first loop is filled as the floor and any further loops are drawn as
+/// holes in it. The first rectangle is treated as the chosen answer and drawn boldly;
+/// any further rectangles are runners-up drawn in progressively fainter
+/// colours behind it.
+///
+/// # Arguments
+/// * `loops` - The red tiles of each loop in order, outer boundary first
+/// * `rects` - Rectangles to highlight, best first
+/// * `size` - Width and height of the canvas in pixels
+///
+/// # Returns
+/// A standalone SVG document.
+pub fn render(loops: &[Vec<Point>], rects: &[Rectangle], size: u32) -> String {
+    let vertices = loops.concat();
+    let scale = Scale::fit(&vertices, size);
+    let mut svg = String::new();
+
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="0 0 {0} {0}">"#,
+        size
+    )
+    .unwrap();
+    writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+
+    for (i, l) in loops.iter().enumerate() {
+        let outline: Vec<String> = l
+            .iter()
+            .map(|p| {
+                let (x, y) = scale.apply(*p);
+                format!("{:.2},{:.2}", x, y)
+            })
+            .collect();
+        let fill = if i == 0 { "palegreen" } else { "white" };
+
+        writeln!(
+            svg,
+            r#"<polygon points="{}" fill="{}" stroke="green" stroke-width="1"/>"#,
+            outline.join(" "),
+            fill
+        )
+        .unwrap();
+    }
+
+    for (i, rect) in rects.iter().enumerate().skip(1).rev() {
+        let opacity = 0.8 * (1.0 - i as f64 / rects.len() as f64) + 0.1;
+        write_rect(&mut svg, &scale, rect, "orange", opacity, 1.0);
+    }
+
+    if let Some(best) = rects.first() {
+        write_rect(&mut svg, &scale, best, "blue", 1.0, 2.0);
+    }
+
+    let radius = (size as f64 / 400.0).max(1.0);
+    for p in &vertices {
+        let (x, y) = scale.apply(*p);
+        writeln!(
+            svg,
+            r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="red"/>"#,
+            x, y, radius
+        )
+        .unwrap();
+    }
+
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+/// Writes one rectangle outline with a translucent fill.
+fn write_rect(
+    svg: &mut String,
+    scale: &Scale,
+    rect: &Rectangle,
+    colour: &str,
+    opacity: f64,
+    width: f64,
+) {
+    let square = rect.square();
+    let (x1, y1) = scale.apply(square.nw);
+    let (x2, y2) = scale.apply(square.se);
+
+    let (x, y, w, h) = (x1, y1, x2 - x1, y2 - y1);
+    let fill_opacity = opacity * 0.3;
+
+    writeln!(
+        svg,
+        r#"<rect x="{x:.2}" y="{y:.2}" width="{w:.2}" height="{h:.2}" fill="{colour}" fill-opacity="{fill_opacity:.2}" stroke="{colour}" stroke-opacity="{opacity:.2}" stroke-width="{width}"/>"#
+    )
+    .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day9::RedTile;
+
+    fn p(x: i64, y: i64) -> Point {
+        Point { x, y }
+    }
+
+    #[test]
+    fn test_scale_fit() {
+        let scale = Scale::fit(&[p(10, 10), p(30, 20)], 220);
+        assert_eq!(scale.apply(p(10, 10)), (10.0, 10.0));
+        assert_eq!(scale.apply(p(30, 20)), (210.0, 110.0));
+    }
+
+    #[test]
+    fn test_render_contents() {
+        let loops = [vec![p(0, 0), p(4, 0), p(4, 4), p(0, 4)]];
+        let tile = |point, line| RedTile { point, line };
+        let best = Rectangle::new(tile(p(0, 0), 1), tile(p(4, 4), 3));
+        let runner_up = Rectangle::new(tile(p(0, 0), 1), tile(p(4, 0), 2));
+
+        let svg = render(&loops, &[best, runner_up], 100);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<circle").count(), 4);
+        assert_eq!(svg.matches(r#"stroke="blue""#).count(), 1);
+        assert_eq!(svg.matches(r#"stroke="orange""#).count(), 1);
+    }
+
+    #[test]
+    fn test_render_holes() {
+        let loops = [
+            vec![p(0, 0), p(8, 0), p(8, 8), p(0, 8)],
+            vec![p(3, 3), p(5, 3), p(5, 5), p(3, 5)],
+        ];
+
+        let svg = render(&loops, &[], 100);
+
+        assert_eq!(svg.matches("<polygon").count(), 2);
+        assert_eq!(svg.matches(r#"fill="white" stroke="green""#).count(), 1);
+        assert_eq!(svg.matches("<circle").count(), 8);
+    }
+}
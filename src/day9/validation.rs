@@ -0,0 +1,405 @@
+//! Validation of the red tile loop before it is turned into an [`Atlas`].
+//!
+//! [`Atlas::new`](super::Atlas::new) assumes the tiles form a simple,
+//! rectilinear, closed loop. This module checks those assumptions up front so
+//! bad input is reported with line numbers instead of panicking mid-search.
+
+use std::fmt;
+
+use super::polygon::{MAX_COORDINATE, Orientation, Polygon, orientation, segments_intersect};
+use super::{Point, RedTile};
+
+/// A problem found in the red tile loop.
+///
+/// Every variant carries the 1-based input line numbers of the tiles involved.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Defect {
+    /// A tile lies further than [`MAX_COORDINATE`] from the origin
+    CoordinateOutOfRange { line: usize },
+    /// The same tile appears more than once
+    DuplicateVertex { lines: [usize; 2] },
+    /// Two consecutive tiles are at the same position
+    ZeroLengthEdge { lines: [usize; 2] },
+    /// Two consecutive tiles share neither a row nor a column
+    DiagonalEdge { lines: [usize; 2] },
+    /// Three consecutive tiles lie on one line, so the middle one isn't a corner
+    CollinearPoints { lines: [usize; 3] },
+    /// The loop encloses no area, e.g. it has fewer than three vertices or
+    /// they all lie on one line; identified by its first tile
+    ZeroArea { line: usize },
+    /// Two non-adjacent edges touch or cross
+    SelfIntersection { edges: [[usize; 2]; 2] },
+    /// Edges from two different loops touch or cross
+    LoopsIntersect { edges: [[usize; 2]; 2] },
+    /// A hole lies outside the outer boundary, identified by its first tile
+    HoleOutside { line: usize },
+    /// A hole lies inside another hole, identified by both first tiles
+    NestedHole { lines: [usize; 2] },
+}
+
+impl fmt::Display for Defect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Defect::CoordinateOutOfRange { line } => write!(
+                f,
+                "tile on line {} is more than {} from the origin",
+                line, MAX_COORDINATE
+            ),
+            Defect::DuplicateVertex { lines: [a, b] } => {
+                write!(f, "duplicate vertex on lines {} and {}", a, b)
+            }
+            Defect::ZeroLengthEdge { lines: [a, b] } => {
+                write!(f, "zero-length edge between lines {} and {}", a, b)
+            }
+            Defect::DiagonalEdge { lines: [a, b] } => {
+                write!(f, "diagonal edge between lines {} and {}", a, b)
+            }
+            Defect::CollinearPoints { lines: [a, b, c] } => {
+                write!(f, "collinear points on lines {}, {} and {}", a, b, c)
+            }
+            Defect::ZeroArea { line } => {
+                write!(f, "loop starting on line {} encloses no area", line)
+            }
+            Defect::SelfIntersection {
+                edges: [[a1, a2], [b1, b2]],
+            } => write!(f, "edge {}-{} intersects edge {}-{}", a1, a2, b1, b2),
+            Defect::LoopsIntersect {
+                edges: [[a1, a2], [b1, b2]],
+            } => write!(
+                f,
+                "edge {}-{} intersects edge {}-{} of another loop",
+                a1, a2, b1, b2
+            ),
+            Defect::HoleOutside { line } => {
+                write!(f, "hole starting on line {} is outside the boundary", line)
+            }
+            Defect::NestedHole { lines: [a, b] } => write!(
+                f,
+                "hole starting on line {} is inside the hole starting on line {}",
+                a, b
+            ),
+        }
+    }
+}
+
+/// Checks that the tiles form a simple rectilinear closed loop.
+///
+/// The loop is closed by connecting the last tile back to the first, exactly
+/// as [`Atlas::new`](super::Atlas::new) does.
+///
+/// # Returns
+/// Every defect found, in the order they were detected. An empty vector
+/// means the loop is valid.
+pub fn validate(tiles: &[RedTile]) -> Vec<Defect> {
+    check_loop(tiles, true)
+}
+
+/// Checks that the tiles form a simple closed loop with any edge directions.
+///
+/// This is the subset of [`validate`] that a general
+/// [`Polygon`](super::Polygon) relies on: diagonal edges and collinear
+/// points are allowed.
+pub fn validate_simple(tiles: &[RedTile]) -> Vec<Defect> {
+    check_loop(tiles, false)
+}
+
+/// Checks a floor made of an outer loop followed by any number of holes.
+///
+/// Each loop is checked on its own (as by [`validate`] when `rectilinear`,
+/// otherwise as by [`validate_simple`]), then the loops are checked against
+/// each other: no two may touch, every hole must lie inside the outer loop,
+/// and no hole may lie inside another.
+pub fn validate_loops(loops: &[Vec<RedTile>], rectilinear: bool) -> Vec<Defect> {
+    let mut defects: Vec<Defect> = loops
+        .iter()
+        .flat_map(|l| check_loop(l, rectilinear))
+        .collect();
+
+    for a in 0..loops.len() {
+        for b in a + 1..loops.len() {
+            for (a1, a2) in edges(&loops[a]) {
+                for (b1, b2) in edges(&loops[b]) {
+                    if segments_intersect(a1.point, a2.point, b1.point, b2.point) {
+                        defects.push(Defect::LoopsIntersect {
+                            edges: [[a1.line, a2.line], [b1.line, b2.line]],
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // Containment is only well defined once no loops cross
+    if !defects.is_empty() {
+        return defects;
+    }
+
+    let Some((outer, holes)) = loops.split_first() else {
+        return defects;
+    };
+
+    let outer = Polygon::new(points(outer));
+    let hole_polygons: Vec<Polygon> = holes.iter().map(|h| Polygon::new(points(h))).collect();
+
+    for (i, hole) in holes.iter().enumerate() {
+        let first = hole[0];
+
+        if !outer.contains(first.point) {
+            defects.push(Defect::HoleOutside { line: first.line });
+        }
+
+        for (j, other) in hole_polygons.iter().enumerate() {
+            if i != j && other.contains(first.point) {
+                defects.push(Defect::NestedHole {
+                    lines: [first.line, holes[j][0].line],
+                });
+            }
+        }
+    }
+
+    defects
+}
+
+/// Returns the edges of a loop as pairs of tiles, including the closing edge.
+fn edges(tiles: &[RedTile]) -> impl Iterator<Item = (RedTile, RedTile)> + '_ {
+    let n = tiles.len();
+
+    (0..n).map(move |i| (tiles[i], tiles[(i + 1) % n]))
+}
+
+/// Extracts the positions of a loop's tiles.
+fn points(tiles: &[RedTile]) -> Vec<Point> {
+    tiles.iter().map(|t| t.point).collect()
+}
+
+/// Runs the loop checks, including the axis-aligned ones if `rectilinear`.
+fn check_loop(tiles: &[RedTile], rectilinear: bool) -> Vec<Defect> {
+    let mut defects = vec![];
+    let n = tiles.len();
+
+    for t in tiles {
+        if t.point.x.unsigned_abs().max(t.point.y.unsigned_abs()) > MAX_COORDINATE as u64 {
+            defects.push(Defect::CoordinateOutOfRange { line: t.line });
+        }
+    }
+
+    // Areas are only exact within range
+    let in_range = defects.is_empty();
+
+    for i in 0..n {
+        for j in i + 1..n {
+            if tiles[i].point == tiles[j].point {
+                defects.push(Defect::DuplicateVertex {
+                    lines: [tiles[i].line, tiles[j].line],
+                });
+            }
+        }
+    }
+
+    for i in 0..n {
+        let t1 = tiles[i];
+        let t2 = tiles[(i + 1) % n];
+        let lines = [t1.line, t2.line];
+
+        if t1.point == t2.point {
+            defects.push(Defect::ZeroLengthEdge { lines });
+        } else if rectilinear && t1.point.x != t2.point.x && t1.point.y != t2.point.y {
+            defects.push(Defect::DiagonalEdge { lines });
+        }
+    }
+
+    if rectilinear {
+        for i in 0..n {
+            let t1 = tiles[i];
+            let t2 = tiles[(i + 1) % n];
+            let t3 = tiles[(i + 2) % n];
+
+            let distinct = t1.point != t2.point && t2.point != t3.point;
+
+            if distinct && orientation(t1.point, t2.point, t3.point) == Orientation::Collinear {
+                defects.push(Defect::CollinearPoints {
+                    lines: [t1.line, t2.line, t3.line],
+                });
+            }
+        }
+    }
+
+    if in_range && Polygon::new(points(tiles)).doubled_area() == 0 {
+        defects.push(Defect::ZeroArea {
+            line: tiles[0].line,
+        });
+    }
+
+    for i in 0..n {
+        for j in i + 1..n {
+            let adjacent = j == i + 1 || (i == 0 && j == n - 1);
+            if adjacent {
+                continue;
+            }
+
+            let (a1, a2) = (tiles[i], tiles[(i + 1) % n]);
+            let (b1, b2) = (tiles[j], tiles[(j + 1) % n]);
+
+            if segments_intersect(a1.point, a2.point, b1.point, b2.point) {
+                defects.push(Defect::SelfIntersection {
+                    edges: [[a1.line, a2.line], [b1.line, b2.line]],
+                });
+            }
+        }
+    }
+
+    defects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day9::Point;
+
+    fn tiles(points: &[(i64, i64)]) -> Vec<RedTile> {
+        tiles_from(points, 1)
+    }
+
+    fn tiles_from(points: &[(i64, i64)], first_line: usize) -> Vec<RedTile> {
+        points
+            .iter()
+            .enumerate()
+            .map(|(i, &(x, y))| RedTile {
+                point: Point { x, y },
+                line: i + first_line,
+            })
+            .collect()
+    }
+
+    fn square(x: i64, y: i64, size: i64, first_line: usize) -> Vec<RedTile> {
+        tiles_from(
+            &[(x, y), (x + size, y), (x + size, y + size), (x, y + size)],
+            first_line,
+        )
+    }
+
+    #[test]
+    fn test_validate_loops_valid_hole() {
+        let loops = vec![square(0, 0, 10, 1), square(3, 3, 2, 6)];
+        assert!(validate_loops(&loops, true).is_empty());
+    }
+
+    #[test]
+    fn test_validate_loops_hole_outside() {
+        let loops = vec![square(0, 0, 10, 1), square(20, 20, 2, 6)];
+        assert_eq!(
+            validate_loops(&loops, true),
+            vec![Defect::HoleOutside { line: 6 }]
+        );
+    }
+
+    #[test]
+    fn test_validate_loops_nested_hole() {
+        let loops = vec![square(0, 0, 10, 1), square(2, 2, 6, 6), square(4, 4, 1, 11)];
+        assert_eq!(
+            validate_loops(&loops, true),
+            vec![Defect::NestedHole { lines: [11, 6] }]
+        );
+    }
+
+    #[test]
+    fn test_validate_loops_crossing() {
+        let loops = vec![square(0, 0, 10, 1), square(8, 4, 4, 6)];
+        let defects = validate_loops(&loops, true);
+        assert!(!defects.is_empty());
+        assert!(
+            defects
+                .iter()
+                .all(|d| matches!(d, Defect::LoopsIntersect { .. }))
+        );
+    }
+
+    #[test]
+    fn test_validate_valid_loop() {
+        let t = tiles(&[
+            (7, 1),
+            (11, 1),
+            (11, 7),
+            (9, 7),
+            (9, 5),
+            (2, 5),
+            (2, 3),
+            (7, 3),
+        ]);
+        assert!(validate(&t).is_empty());
+    }
+
+    #[test]
+    fn test_validate_diagonal_edge() {
+        let t = tiles(&[(0, 0), (4, 0), (4, 4), (1, 3), (0, 3)]);
+        let defects = validate(&t);
+        assert!(defects.contains(&Defect::DiagonalEdge { lines: [3, 4] }));
+    }
+
+    #[test]
+    fn test_validate_duplicate_and_zero_length() {
+        let t = tiles(&[(0, 0), (4, 0), (4, 0), (4, 4), (0, 4)]);
+        let defects = validate(&t);
+        assert!(defects.contains(&Defect::DuplicateVertex { lines: [2, 3] }));
+        assert!(defects.contains(&Defect::ZeroLengthEdge { lines: [2, 3] }));
+    }
+
+    #[test]
+    fn test_validate_collinear() {
+        let t = tiles(&[(0, 0), (2, 0), (4, 0), (4, 4), (0, 4)]);
+        assert_eq!(
+            validate(&t),
+            vec![Defect::CollinearPoints { lines: [1, 2, 3] }]
+        );
+    }
+
+    #[test]
+    fn test_validate_simple_allows_diagonal() {
+        let t = tiles(&[(0, 0), (4, 0), (4, 4), (1, 3), (0, 3)]);
+        assert!(validate_simple(&t).is_empty());
+    }
+
+    #[test]
+    fn test_validate_self_intersection() {
+        // The edge from line 4 to 5 cuts straight through the edge from 1 to 2
+        let t = tiles(&[(0, 0), (4, 0), (4, 4), (2, 4), (2, -2), (0, -2)]);
+        assert_eq!(
+            validate(&t),
+            vec![Defect::SelfIntersection {
+                edges: [[1, 2], [4, 5]]
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_zero_area() {
+        let t = tiles(&[(0, 0), (5, 0), (9, 0)]);
+        assert_eq!(validate_simple(&t), vec![Defect::ZeroArea { line: 1 }]);
+        assert_eq!(
+            validate_simple(&tiles(&[(3, 3)])),
+            vec![
+                Defect::ZeroLengthEdge { lines: [1, 1] },
+                Defect::ZeroArea { line: 1 }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_coordinate_out_of_range() {
+        let far = MAX_COORDINATE + 1;
+        let t = tiles(&[(0, 0), (far, 0), (far, 4), (0, 4)]);
+        assert_eq!(
+            validate_simple(&t),
+            vec![
+                Defect::CoordinateOutOfRange { line: 2 },
+                Defect::CoordinateOutOfRange { line: 3 }
+            ]
+        );
+
+        let t = tiles(&[(0, 0), (i64::MIN, 0), (0, 4)]);
+        assert_eq!(
+            validate_simple(&t),
+            vec![Defect::CoordinateOutOfRange { line: 2 }]
+        );
+    }
+}
@@ -7,31 +7,42 @@ mod day5;
 mod day6;
 mod day7;
 mod day8;
-mod day9;
+pub mod day9;
+mod params;
 
 use crate::{
     day::Day, day1::Day1, day2::Day2, day3::Day3, day4::Day4, day5::Day5, day6::Day6, day7::Day7,
     day8::Day8, day9::Day9,
 };
 
-pub fn run(lines: Vec<String>, day: &str) {
+pub use crate::params::Params;
+
+pub fn run(lines: Vec<String>, day: &str, mode: Option<&str>, params: &Params) {
     match day {
-        "day1" => run_day::<Day1>(lines),
-        "day2" => run_day::<Day2>(lines),
-        "day3" => run_day::<Day3>(lines),
-        "day4" => run_day::<Day4>(lines),
-        "day5" => run_day::<Day5>(lines),
-        "day6" => run_day::<Day6>(lines),
-        "day7" => run_day::<Day7>(lines),
-        "day8" => run_day::<Day8>(lines),
-        "day9" => run_day::<Day9>(lines),
+        "day1" => run_day::<Day1>(lines, mode, params),
+        "day2" => run_day::<Day2>(lines, mode, params),
+        "day3" => run_day::<Day3>(lines, mode, params),
+        "day4" => run_day::<Day4>(lines, mode, params),
+        "day5" => run_day::<Day5>(lines, mode, params),
+        "day6" => run_day::<Day6>(lines, mode, params),
+        "day7" => run_day::<Day7>(lines, mode, params),
+        "day8" => run_day::<Day8>(lines, mode, params),
+        "day9" => run_day::<Day9>(lines, mode, params),
         _ => panic!("Day [{}] isn't supported", day),
     }
 }
 
-fn run_day<T: Day>(lines: Vec<String>) {
-    let res_a = T::part_a(&lines[..]);
-    let res_b = T::part_b(&lines[..]);
+fn run_day<T: Day>(lines: Vec<String>, mode: Option<&str>, params: &Params) {
+    if let Some(mode) = mode {
+        match T::mode(&lines[..], mode, params) {
+            Ok(out) => println!("{}", out),
+            Err(e) => panic!("{}", e),
+        }
+        return;
+    }
+
+    let res_a = T::part_a_with(&lines[..], params).unwrap();
+    let res_b = T::part_b_with(&lines[..], params).unwrap();
 
     println!("a: {}", res_a);
     println!("b: {}", res_b);
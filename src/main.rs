@@ -1,4 +1,4 @@
-use advent_of_code_2025::run;
+use advent_of_code_2025::{Params, run};
 use std::io;
 
 use clap::Parser;
@@ -8,6 +8,14 @@ use clap::Parser;
 struct Cli {
     /// The day to run
     day: String,
+
+    /// Run a day-specific output mode instead of parts a and b
+    #[arg(short, long)]
+    mode: Option<String>,
+
+    /// Extra `key=value` parameter for the day (may be repeated)
+    #[arg(short, long = "param")]
+    params: Vec<String>,
 }
 
 fn main() {
@@ -21,5 +29,7 @@ fn main() {
 
     let day = args.day.as_str();
 
-    run(lines, day);
+    let params = Params::parse(&args.params).unwrap();
+
+    run(lines, day, args.mode.as_deref(), &params);
 }
@@ -0,0 +1,116 @@
+//! Extra `key=value` parameters passed to a day on the command line.
+//!
+//! Days read whichever keys they understand from [`Params`]; values are kept
+//! as strings until a day asks for them as a concrete type.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::{Context, Result, anyhow};
+
+/// A set of named parameters for a day's solution or output mode.
+#[derive(Debug, Default, Clone)]
+pub struct Params {
+    values: HashMap<String, String>,
+}
+
+impl Params {
+    /// Parses parameters from `key=value` strings.
+    ///
+    /// A bare `key` (with no `=`) is treated as the flag `key=true`.
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let mut values = HashMap::new();
+
+        for arg in args {
+            let (key, value) = arg.split_once('=').unwrap_or((arg, "true"));
+
+            if key.is_empty() {
+                return Err(anyhow!("Parameter [{}] has no name", arg));
+            }
+
+            values.insert(key.to_string(), value.to_string());
+        }
+
+        Ok(Self { values })
+    }
+
+    /// Parses parameters from string literals, for tests.
+    ///
+    /// # Panics
+    /// If any parameter is invalid.
+    #[cfg(test)]
+    pub fn from_strs(args: &[&str]) -> Self {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+
+        Self::parse(&args).unwrap()
+    }
+
+    /// Gets a parameter parsed as `T`, or `None` if it wasn't given.
+    pub fn get<T>(&self, key: &str) -> Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let Some(value) = self.values.get(key) else {
+            return Ok(None);
+        };
+
+        value
+            .parse()
+            .map(Some)
+            .map_err(|e| anyhow!("Parameter [{}={}] is invalid: {}", key, value, e))
+    }
+
+    /// Gets a parameter parsed as `T`, falling back to `default`.
+    pub fn get_or<T>(&self, key: &str, default: T) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        Ok(self.get(key)?.unwrap_or(default))
+    }
+
+    /// Gets a parameter parsed as `T`, failing if it wasn't given.
+    pub fn require<T>(&self, key: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.get(key)?
+            .with_context(|| format!("Parameter [{}] is required", key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_parses_value() {
+        let p = Params::from_strs(&["top=5", "out=a.svg"]);
+        assert_eq!(p.get::<usize>("top").unwrap(), Some(5));
+        assert_eq!(p.get::<String>("out").unwrap(), Some("a.svg".to_string()));
+        assert_eq!(p.get::<usize>("missing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_bare_key_is_flag() {
+        let p = Params::from_strs(&["verbose"]);
+        assert!(p.get_or("verbose", false).unwrap());
+        assert!(!p.get_or("quiet", false).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_value() {
+        let p = Params::from_strs(&["top=five"]);
+        assert!(p.get::<usize>("top").is_err());
+        assert!(p.require::<usize>("size").is_err());
+    }
+
+    #[test]
+    fn test_empty_key() {
+        let args = vec!["=5".to_string()];
+        assert!(Params::parse(&args).is_err());
+    }
+}